@@ -1,3 +1,5 @@
+use reshaderlib::{link::LinkMode, reshade_ini::Section};
+
 #[derive(Debug, clap::Parser)]
 #[command(author, version, about)]
 pub struct CliArgs {
@@ -23,14 +25,82 @@ pub enum SubCommand {
         #[arg(short, long)]
         version: Option<String>,
         /// Install the ReShade library for this game. If this isn't set, the installer will only download ReShade.
+        ///
+        /// ReShade is installed next to the game executable, which is detected inside this directory.
         #[arg(short, long)]
         game: Option<String>,
+        /// Install the ReShade library next to this game executable instead of detecting it
+        #[arg(short, long)]
+        executable: Option<String>,
+        /// Add DLL overrides for ReShade to the user.reg of this Wine prefix (Wine must not be running)
+        #[arg(long)]
+        wine_prefix: Option<String>,
+        /// Only add the DLL overrides for this executable (e.g. ffxiv_dx11.exe) instead of the whole prefix
+        #[arg(long, requires = "wine_prefix")]
+        wine_executable: Option<String>,
+        /// Let ReShade load the dxgi.dll it replaces, e.g. the one of DXVK
+        #[arg(long)]
+        chain_load: bool,
+        /// How to put the files into the game directory (default: the mode used for the game before, or symlink)
+        #[arg(long)]
+        link_mode: Option<LinkMode>,
+        /// Keep the game at this version when all games are updated
+        #[arg(long, requires = "version")]
+        pin: bool,
+        #[command(flatten)]
+        plan: PlanOptions,
     },
     /// Install ReShade shaders for a game
     InstallReshadeShaders {
         /// Install the ReShade library for this game. If this isn't set, the installer will only download ReShade.
         #[arg(short, long)]
         game: Option<String>,
+        /// How to put the files into the game directory (default: the mode used for the game before, or symlink)
+        #[arg(long)]
+        link_mode: Option<LinkMode>,
+        #[command(flatten)]
+        plan: PlanOptions,
+    },
+    /// Install the shader collections a ReShade preset needs
+    ///
+    /// The first run downloads every known shader collection once to find out which effects it contains.
+    InstallPresetShaders {
+        /// Location of the preset file
+        #[arg(short, long)]
+        preset: String,
+        /// Install the shaders for this game. If this isn't set, the shaders will only be downloaded.
+        #[arg(short, long)]
+        game: Option<String>,
+        /// Download all shader collections again to update the index of their effects
+        #[arg(long)]
+        refresh_index: bool,
+        /// How to put the files into the game directory (default: the mode used for the game before, or symlink)
+        #[arg(long)]
+        link_mode: Option<LinkMode>,
+        #[command(flatten)]
+        plan: PlanOptions,
+    },
+    /// Check that the shaders and textures a preset uses are installed for a game
+    ValidatePreset {
+        /// Location of the preset file
+        #[arg(short, long)]
+        preset: String,
+        /// The game directory, its ReShade.ini decides where shaders are searched
+        #[arg(short, long)]
+        game: String,
+    },
+    /// Rewrite GShade presets to use the effects of the ReShade shader collections
+    ///
+    /// Presets are changed in place after a backup is made, unless an output directory is given.
+    ConvertPresets {
+        /// The preset files or directories to convert (default: the installed GShade presets)
+        paths: Vec<String>,
+        /// Write the converted presets to this directory instead
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Use this mapping table instead of the one shipped with ReShader
+        #[arg(short, long)]
+        mapping: Option<String>,
     },
     /// Install GShade presets and shaders for a game. If no game is specified and all is not set, the presets and shaders will only be downloaded.
     InstallPresets {
@@ -40,17 +110,226 @@ pub enum SubCommand {
         /// Install the presets and shaders for a specific game (if all is specified, this argument is ignored)
         #[arg(short, long)]
         game: Option<String>,
-        /// Location of the GShade presets zip file
+        /// Location of the GShade presets (zip file or folder)
         #[arg(short, long, required = true)]
         presets: String,
-        /// Location of the GShade shaders zip file
+        /// Only install the presets in this subfolder of the presets (default: FFXIV if the presets contain it)
+        #[arg(long)]
+        presets_subfolder: Option<String>,
+        /// Install the presets of all games instead of a single subfolder
+        #[arg(long, conflicts_with = "presets_subfolder")]
+        all_presets: bool,
+        /// Location of the GShade shaders (zip file or folder)
         #[arg(short, long, required = true)]
         shaders: String,
+        /// How to put the files into the game directory (default: the mode used for the game before, or symlink)
+        #[arg(long)]
+        link_mode: Option<LinkMode>,
+        #[command(flatten)]
+        plan: PlanOptions,
     },
     /// Uninstall ReShade or GShade from a game
     Uninstall {
         /// Uninstall from this game
         #[arg(short, long)]
         game: String,
+        /// Remove the DLL overrides for ReShade from the user.reg of this Wine prefix (Wine must not be running).
+        /// Without --wine-executable, they are kept while other registered games use the prefix
+        #[arg(long)]
+        wine_prefix: Option<String>,
+        /// Only remove the DLL overrides for this executable (e.g. ffxiv_dx11.exe) instead of the whole prefix
+        #[arg(long, requires = "wine_prefix")]
+        wine_executable: Option<String>,
+        #[command(flatten)]
+        plan: PlanOptions,
+    },
+    /// Apply the changes printed by `--dry-run --json` to a registered game
+    ApplyPlan {
+        /// Location of the JSON file
+        plan: String,
+    },
+    /// Launch a game with ReShade, e.g. `reshader run -- %command%` in the Steam launch options
+    ///
+    /// Repairs the ReShade symlinks of the game if needed and sets WINEDLLOVERRIDES before running the command.
+    Run {
+        /// The game directory (default: detected from the working directory and the command)
+        #[arg(short, long)]
+        game: Option<String>,
+        /// The command to run
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Update ReShade, the shader collections and the GShade presets of games
+    ///
+    /// Games pinned to a ReShade version are kept at it, and every game keeps its link mode.
+    Update {
+        /// Update this game
+        #[arg(short, long, required_unless_present = "all")]
+        game: Option<String>,
+        /// Update all games ReShade was installed for
+        #[arg(short, long, conflicts_with = "game")]
+        all: bool,
+    },
+    /// Show what is installed for the games ReShade was installed for
+    Status {
+        /// Only show this game
+        #[arg(short, long)]
+        game: Option<String>,
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the games ReShade was installed for and suggest how to fix their problems
+    Doctor {
+        /// Only check this game
+        #[arg(short, long)]
+        game: Option<String>,
+        /// Check the DLL overrides in this Wine prefix (default: detected from the game directory)
+        #[arg(long)]
+        wine_prefix: Option<String>,
+        /// Fix the problems that can be fixed automatically (Wine must not be running)
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Remove what none of the games needs from the data directory
+    ///
+    /// This covers unused ReShade versions and DLLs, shaders and presets no game links to,
+    /// cached downloads and files left behind by interrupted operations.
+    Gc {
+        /// Remove the files without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Read and change the ReShade.ini of one or all games
+    Ini {
+        #[clap(subcommand)]
+        action: IniAction,
+    },
+    /// Manage the presets kept by ReShader
+    Presets {
+        #[clap(subcommand)]
+        action: PresetsAction,
+    },
+    /// Find shaders by their technique, effect file, label or description
+    Shaders {
+        #[clap(subcommand)]
+        action: ShadersAction,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+pub struct PlanOptions {
+    /// Only print the changes to the game directories instead of applying them (downloads still happen)
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Print the changes as JSON, which can be applied with `apply-plan`
+    #[arg(long, requires = "dry_run")]
+    pub json: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct IniTarget {
+    /// Use the ReShade.ini of this game
+    #[arg(short, long, required_unless_present = "all")]
+    pub game: Option<String>,
+    /// Use the ReShade.ini of all games ReShade was installed for
+    #[arg(short, long, conflicts_with = "game")]
+    pub all: bool,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum IniAction {
+    /// Print the value of a key
+    Get {
+        /// The section of the key, e.g. GENERAL
+        section: Section,
+        /// The key, e.g. PresetPath
+        key: String,
+        #[command(flatten)]
+        target: IniTarget,
+    },
+    /// Set the value of a key
+    Set {
+        /// The section of the key, e.g. SCREENSHOT
+        section: Section,
+        /// The key, e.g. SavePath
+        key: String,
+        /// The new value
+        value: String,
+        /// Allow keys ReShader doesn't know about
+        #[arg(long)]
+        allow_unknown: bool,
+        #[command(flatten)]
+        target: IniTarget,
+    },
+    /// Add an entry to a list, e.g. a search path or a preprocessor definition
+    ///
+    /// Preprocessor definitions with the same name are replaced.
+    Add {
+        /// The section of the key, e.g. GENERAL
+        section: Section,
+        /// The key, e.g. PreprocessorDefinitions
+        key: String,
+        /// The entry to add, e.g. RESHADE_DEPTH_INPUT_IS_REVERSED=0
+        value: String,
+        #[command(flatten)]
+        target: IniTarget,
+    },
+    /// Remove an entry from a list
+    ///
+    /// Preprocessor definitions can be removed by their name alone.
+    Remove {
+        /// The section of the key, e.g. GENERAL
+        section: Section,
+        /// The key, e.g. EffectSearchPaths
+        key: String,
+        /// The entry to remove
+        value: String,
+        #[command(flatten)]
+        target: IniTarget,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum ShadersAction {
+    /// Scan the downloaded shaders and update the catalog
+    Scan,
+    /// Search the catalog, which is created on the first search
+    Search {
+        /// The text to look for, e.g. sharpen
+        term: String,
+        /// Scan the downloaded shaders again before searching
+        #[arg(long)]
+        rescan: bool,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum PresetsAction {
+    /// Import presets from a preset file, a zip file or a directory
+    Import {
+        /// Location of the preset file, zip file or directory
+        source: String,
+    },
+    /// List all imported presets
+    List,
+    /// Find imported presets by their name or the techniques they use
+    Search {
+        /// The text to look for, e.g. bloom
+        term: String,
+    },
+    /// Copy an imported preset to another location
+    Export {
+        /// The name of the preset, as shown by list
+        name: String,
+        /// The file or directory to copy the preset to
+        destination: String,
+    },
+    /// Make a game load an imported preset by setting PresetPath in its ReShade.ini
+    Apply {
+        /// The name of the preset, as shown by list
+        name: String,
+        #[command(flatten)]
+        target: IniTarget,
     },
 }
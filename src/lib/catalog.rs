@@ -0,0 +1,214 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collection_index::CollectionIndex,
+    effect::{parse_effect, EffectInfo, EffectTechnique},
+    prelude::*,
+    write_atomically, ShaderCollection,
+};
+
+/// The name of the file the shader catalog is stored in, inside the data directory
+pub const CATALOG_FILE: &str = "shader-catalog.json";
+
+/// The collection name used for the shaders installed from GShade
+pub const GSHADE_COLLECTION: &str = "GShade";
+
+/// Every effect file ReShader has downloaded, with the techniques and uniforms they declare
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShaderCatalog {
+    /// The effect files found in the data directory
+    pub effects: Vec<CatalogEffect>,
+}
+
+/// An effect file in the [`ShaderCatalog`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogEffect {
+    /// The path of the effect file, relative to the data directory
+    pub path: PathBuf,
+    /// The file name of the effect, e.g. `SMAA.fx`
+    pub file: String,
+    /// The name of the shader collection the effect belongs to, if known
+    pub collection: Option<String>,
+    /// What the effect file declares
+    pub info: EffectInfo,
+}
+
+/// A technique found by [`ShaderCatalog::search`]
+#[derive(Debug, Clone)]
+pub struct CatalogMatch<'a> {
+    /// The effect file declaring the technique
+    pub effect: &'a CatalogEffect,
+    /// The matching technique
+    pub technique: &'a EffectTechnique,
+}
+
+impl ShaderCatalog {
+    /// Reads a shader catalog
+    pub fn load(path: &Path) -> ReShaderResult<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| ReShaderError::Index(e.to_string()))
+    }
+
+    /// Writes the shader catalog
+    pub fn save(&self, path: &Path) -> ReShaderResult<()> {
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| ReShaderError::Index(e.to_string()))?;
+        write_atomically(path, content.as_bytes())
+    }
+
+    /// Scans the shaders downloaded to the data directory
+    ///
+    /// Effects are attributed to a collection using the collection index if there is one,
+    /// otherwise by the install path of the collection.
+    pub fn scan(
+        data_dir: &Path,
+        collections: &[ShaderCollection],
+        index: Option<&CollectionIndex>,
+    ) -> ReShaderResult<Self> {
+        let mut catalog = ShaderCatalog::default();
+
+        for root in ["Merged", "reshade-shaders"] {
+            let mut files = Vec::new();
+            collect_effect_files(&data_dir.join(root), &mut HashSet::new(), &mut files)?;
+            files.sort();
+
+            for path in files {
+                let source = std::fs::read(&path)?;
+                let relative = path.strip_prefix(data_dir).unwrap().to_path_buf();
+                let file = path.file_name().unwrap().to_string_lossy().to_string();
+                let collection = if root == "reshade-shaders" {
+                    Some(GSHADE_COLLECTION.to_string())
+                } else {
+                    find_collection(&relative, &file, collections, index)
+                };
+
+                catalog.effects.push(CatalogEffect {
+                    path: relative,
+                    file,
+                    collection,
+                    info: parse_effect(&String::from_utf8_lossy(&source)),
+                });
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    /// Returns an effect file of the shader collections, ignoring the shaders installed from GShade
+    pub fn effect(&self, file: &str) -> Option<&CatalogEffect> {
+        self.effects.iter().find(|effect| {
+            effect.file.eq_ignore_ascii_case(file)
+                && effect.collection.as_deref() != Some(GSHADE_COLLECTION)
+        })
+    }
+
+    /// Finds techniques by their name, effect file, label or tooltip, ignoring case
+    pub fn search(&self, term: &str) -> Vec<CatalogMatch<'_>> {
+        let term = term.to_lowercase();
+        let matches = |text: &str| text.to_lowercase().contains(&term);
+
+        self.effects
+            .iter()
+            .flat_map(|effect| {
+                let file_matches = matches(&effect.file);
+                effect
+                    .info
+                    .techniques
+                    .iter()
+                    .filter(move |technique| {
+                        file_matches
+                            || matches(&technique.name)
+                            || technique.label().is_some_and(matches)
+                            || technique.tooltip().is_some_and(matches)
+                    })
+                    .map(move |technique| CatalogMatch { effect, technique })
+            })
+            .collect()
+    }
+}
+
+/// Collects the effect files inside a directory, following symlinks but visiting every directory only once
+fn collect_effect_files(
+    directory: &Path,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> ReShaderResult<()> {
+    // a symlink to a parent directory would otherwise be followed forever
+    if !directory.is_dir() || !visited.insert(directory.canonicalize()?) {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_effect_files(&path, visited, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("fx"))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn find_collection(
+    relative: &Path,
+    file: &str,
+    collections: &[ShaderCollection],
+    index: Option<&CollectionIndex>,
+) -> Option<String> {
+    // collections sharing an install path can only be told apart by the index
+    let indexed = index.and_then(|index| {
+        index.collections.iter().find(|collection| {
+            collection
+                .effects
+                .iter()
+                .any(|effect| effect.file.eq_ignore_ascii_case(file))
+        })
+    });
+    if let Some(indexed) = indexed {
+        return Some(indexed.name.clone());
+    }
+
+    collections
+        .iter()
+        .filter(|collection| relative.starts_with(&collection.install_path))
+        .max_by_key(|collection| collection.install_path.len())
+        .map(|collection| collection.name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn scan_survives_symlink_loops() {
+        let tmp = TempDir::new("reshader-catalog-loop").unwrap();
+        let data_dir = tmp.path();
+        let shaders = data_dir.join("Merged").join("Shaders");
+        std::fs::create_dir_all(&shaders).unwrap();
+        std::fs::write(shaders.join("Bloom.fx"), "technique Bloom {}").unwrap();
+        std::os::unix::fs::symlink("..", shaders.join("Parent")).unwrap();
+
+        let catalog = ShaderCatalog::scan(data_dir, &[], None).unwrap();
+        catalog.save(&data_dir.join(CATALOG_FILE)).unwrap();
+
+        assert_eq!(catalog.effects.len(), 1);
+        assert_eq!(catalog.effects[0].file, "Bloom.fx");
+        assert_eq!(
+            ShaderCatalog::load(&data_dir.join(CATALOG_FILE))
+                .unwrap()
+                .effects
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use std::{io::Read, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    effect::parse_effect,
+    prelude::*,
+    preset::{Preset, Technique},
+    ShaderCollection,
+};
+
+/// The name of the file the collection index is stored in, inside the data directory
+pub const INDEX_FILE: &str = "collection-index.json";
+
+/// Resolving presets with more candidate collections than this falls back to a greedy search
+const MAX_EXACT_CANDIDATES: usize = 12;
+
+/// Which effect files and techniques each shader collection ships
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollectionIndex {
+    /// The collections that could be indexed
+    pub collections: Vec<IndexedCollection>,
+    /// The names of the collections that could not be downloaded
+    pub failed: Vec<String>,
+}
+
+/// The effect files of a single shader collection
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexedCollection {
+    /// The name of the shader collection, see [`ShaderCollection::name`]
+    pub name: String,
+    /// The effect files shipped by the collection
+    pub effects: Vec<IndexedEffect>,
+}
+
+/// An effect file shipped by a shader collection
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexedEffect {
+    /// The file name of the effect, e.g. `SMAA.fx`
+    pub file: String,
+    /// The names of the techniques declared in the effect file
+    pub techniques: Vec<String>,
+}
+
+/// The shader collections needed for a preset, see [`resolve_preset`]
+#[derive(Debug)]
+pub struct PresetResolution<'a> {
+    /// The collections to install, including the required ones
+    pub collections: Vec<&'a ShaderCollection>,
+    /// The techniques no known collection provides
+    pub unsatisfied: Vec<Technique>,
+}
+
+impl CollectionIndex {
+    /// Reads a collection index
+    pub fn load(path: &Path) -> ReShaderResult<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| ReShaderError::Index(e.to_string()))
+    }
+
+    /// Writes the collection index
+    pub fn save(&self, path: &Path) -> ReShaderResult<()> {
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| ReShaderError::Index(e.to_string()))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Returns the index of the collection with the given name
+    pub fn collection(&self, name: &str) -> Option<&IndexedCollection> {
+        self.collections
+            .iter()
+            .find(|collection| collection.name == name)
+    }
+}
+
+impl IndexedCollection {
+    /// Returns whether the collection provides a technique
+    ///
+    /// An effect file of the collection has to declare a technique with that name. If the technique names
+    /// its effect file, it has to be declared in that file, otherwise any effect file is accepted.
+    pub fn provides(&self, technique: &Technique) -> bool {
+        self.effects.iter().any(|effect| {
+            technique
+                .file
+                .as_ref()
+                .is_none_or(|file| effect.file.eq_ignore_ascii_case(file))
+                && effect.techniques.contains(&technique.name)
+        })
+    }
+}
+
+/// Indexes the effect files inside a downloaded shader collection zip
+pub fn index_collection_zip(name: &str, zip_path: &Path) -> ReShaderResult<IndexedCollection> {
+    let file = std::fs::File::open(zip_path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut collection = IndexedCollection {
+        name: name.to_string(),
+        effects: Vec::new(),
+    };
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let Some(path) = entry.enclosed_name().map(Path::to_path_buf) else {
+            continue;
+        };
+        let is_effect = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("fx"));
+        if !entry.is_file() || !is_effect {
+            continue;
+        }
+
+        let mut source = Vec::new();
+        entry.read_to_end(&mut source)?;
+        let info = parse_effect(&String::from_utf8_lossy(&source));
+
+        collection.effects.push(IndexedEffect {
+            file: path.file_name().unwrap().to_string_lossy().to_string(),
+            techniques: info
+                .techniques
+                .into_iter()
+                .map(|technique| technique.name)
+                .collect(),
+        });
+    }
+
+    Ok(collection)
+}
+
+/// Downloads the given shader collections and indexes their effect files
+///
+/// Collections that can't be downloaded are listed in [`CollectionIndex::failed`].
+pub async fn build_collection_index(
+    collections: &[&ShaderCollection],
+) -> ReShaderResult<CollectionIndex> {
+    let tmp = tempdir::TempDir::new("reshader_index")?;
+    let mut index = CollectionIndex::default();
+
+    for collection in collections {
+        if collection.download(tmp.path()).await.is_err() {
+            index.failed.push(collection.name.clone());
+            continue;
+        }
+
+        let zip_path = tmp.path().join(format!("{}.zip", &collection.name));
+        match index_collection_zip(&collection.name, &zip_path) {
+            Ok(indexed) => index.collections.push(indexed),
+            Err(_) => index.failed.push(collection.name.clone()),
+        }
+        std::fs::remove_file(zip_path)?;
+    }
+
+    Ok(index)
+}
+
+/// Works out the smallest set of shader collections that provides every technique enabled in a preset
+///
+/// Required collections are always part of the result.
+pub fn resolve_preset<'a>(
+    preset: &Preset,
+    index: &CollectionIndex,
+    collections: &'a [ShaderCollection],
+) -> PresetResolution<'a> {
+    let mut techniques = preset.techniques();
+    let mut seen = std::collections::HashSet::new();
+    techniques.retain(|technique| seen.insert(technique.clone()));
+
+    let provides = |collection: &ShaderCollection, technique: &Technique| {
+        index
+            .collection(&collection.name)
+            .is_some_and(|indexed| indexed.provides(technique))
+    };
+
+    let mut result = collections
+        .iter()
+        .filter(|collection| collection.required)
+        .collect::<Vec<_>>();
+
+    let mut unsatisfied = Vec::new();
+    let mut remaining = Vec::new();
+    for technique in techniques {
+        if result
+            .iter()
+            .any(|collection| provides(collection, &technique))
+        {
+            continue;
+        }
+        if collections
+            .iter()
+            .any(|collection| provides(collection, &technique))
+        {
+            remaining.push(technique);
+        } else {
+            unsatisfied.push(technique);
+        }
+    }
+
+    // only collections providing at least one missing technique are worth considering
+    let candidates = collections
+        .iter()
+        .filter(|collection| !collection.required)
+        .map(|collection| {
+            let covered = remaining
+                .iter()
+                .map(|technique| provides(collection, technique))
+                .collect::<Vec<_>>();
+            (collection, covered)
+        })
+        .filter(|(_, covered)| covered.iter().any(|covered| *covered))
+        .collect::<Vec<_>>();
+
+    let chosen = if candidates.len() <= MAX_EXACT_CANDIDATES {
+        smallest_cover(&candidates, remaining.len())
+    } else {
+        greedy_cover(&candidates, remaining.len())
+    };
+    result.extend(chosen.into_iter().map(|i| candidates[i].0));
+
+    PresetResolution {
+        collections: result,
+        unsatisfied,
+    }
+}
+
+/// Finds the smallest combination of candidates covering all requirements by trying all of them
+fn smallest_cover(
+    candidates: &[(&ShaderCollection, Vec<bool>)],
+    requirements: usize,
+) -> Vec<usize> {
+    let mut best: Option<u32> = None;
+    for mask in 0u32..(1 << candidates.len()) {
+        if best.is_some_and(|best| best.count_ones() <= mask.count_ones()) {
+            continue;
+        }
+        let covers_all = (0..requirements).all(|requirement| {
+            candidates
+                .iter()
+                .enumerate()
+                .any(|(i, (_, covered))| mask & (1 << i) != 0 && covered[requirement])
+        });
+        if covers_all {
+            best = Some(mask);
+        }
+    }
+
+    let best = best.unwrap_or_default();
+    (0..candidates.len())
+        .filter(|i| best & (1 << i) != 0)
+        .collect()
+}
+
+/// Repeatedly picks the candidate covering the most uncovered requirements
+fn greedy_cover(candidates: &[(&ShaderCollection, Vec<bool>)], requirements: usize) -> Vec<usize> {
+    let mut uncovered = vec![true; requirements];
+    let mut chosen = Vec::new();
+
+    while uncovered.iter().any(|uncovered| *uncovered) {
+        let best = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, (_, covered))| {
+                let count = (0..requirements)
+                    .filter(|&requirement| uncovered[requirement] && covered[requirement])
+                    .count();
+                (i, count)
+            })
+            .max_by_key(|(_, count)| *count);
+
+        match best {
+            Some((i, count)) if count > 0 => {
+                for (requirement, uncovered) in uncovered.iter_mut().enumerate() {
+                    if candidates[i].1[requirement] {
+                        *uncovered = false;
+                    }
+                }
+                chosen.push(i);
+            }
+            _ => break,
+        }
+    }
+
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provides_needs_the_technique_in_the_named_file() {
+        let collection = IndexedCollection {
+            name: "SweetFX".to_string(),
+            effects: vec![IndexedEffect {
+                file: "Vibrance.fx".to_string(),
+                techniques: vec!["Vibrance".to_string()],
+            }],
+        };
+
+        assert!(collection.provides(&Technique::new("Vibrance", Some("vibrance.fx"))));
+        assert!(collection.provides(&Technique::new("Vibrance", None)));
+        // a file of the same name from another collection may declare other techniques
+        assert!(!collection.provides(&Technique::new("VibranceHDR", Some("Vibrance.fx"))));
+        assert!(!collection.provides(&Technique::new("Vibrance", Some("Other.fx"))));
+    }
+}
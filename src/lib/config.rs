@@ -0,0 +1,498 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    executable::{find_executables, Architecture, GameExecutable},
+    link::LinkMode,
+    prelude::*,
+    status::Variant,
+    write_atomically,
+};
+use serde::{Deserialize, Serialize};
+
+/// The version of the config format written by this version of ReShader
+pub const CONFIG_VERSION: u32 = 2;
+
+/// The games ReShader manages, stored in `config.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// The version of the config format, see [`CONFIG_VERSION`]
+    pub version: u32,
+    /// The games ReShade was installed for
+    #[serde(default)]
+    pub games: Vec<GameEntry>,
+}
+
+/// A game ReShade was installed for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameEntry {
+    /// The name of the game
+    pub name: String,
+    /// The directory ReShade is installed to
+    pub path: PathBuf,
+    /// The file name of the main executable
+    pub executable: Option<String>,
+    /// The architecture of the main executable
+    pub architecture: Option<Architecture>,
+    /// The graphics API ReShade hooks into
+    pub api: Option<GraphicsApi>,
+    /// The installed ReShade build
+    pub variant: Option<Variant>,
+    /// The ReShade version the game is kept at when updating
+    pub pinned_version: Option<String>,
+    /// How files are put into the game directory
+    #[serde(default)]
+    pub link_mode: LinkMode,
+    /// The names of the shader collections installed for the game
+    #[serde(default)]
+    pub collections: Vec<String>,
+    /// The launcher the game is started with
+    pub launcher: Option<Launcher>,
+}
+
+/// A graphics API ReShade can hook into, which decides the DLL it is loaded as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsApi {
+    /// Direct3D 9 (`d3d9.dll`)
+    D3d9,
+    /// Direct3D 10, 11 and 12 (`dxgi.dll`)
+    Dxgi,
+    /// OpenGL (`opengl32.dll`)
+    OpenGl,
+    /// Vulkan, loaded as a layer
+    Vulkan,
+}
+
+/// A launcher games are started with on Linux
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Launcher {
+    /// Steam with Proton
+    Steam,
+    /// Lutris
+    Lutris,
+    /// Heroic Games Launcher
+    Heroic,
+    /// Bottles
+    Bottles,
+    /// XIVLauncher.Core for Final Fantasy XIV
+    #[serde(rename = "xlcore")]
+    XlCore,
+}
+
+/// The config before games had their own entries, it had no version
+#[derive(Debug, Deserialize)]
+struct ConfigV1 {
+    game_paths: Vec<String>,
+    #[serde(default)]
+    link_modes: BTreeMap<String, LinkMode>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            games: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config, migrating it from an older format if needed
+    ///
+    /// Returns where the old file was backed up if it had to be migrated.
+    pub fn load(path: &Path) -> ReShaderResult<(Self, Option<PathBuf>)> {
+        let content = std::fs::read_to_string(path)?;
+        let value = toml::from_str::<toml::Value>(&content)
+            .map_err(|e| ReShaderError::Config(e.to_string()))?;
+        let version = match value.get("version") {
+            Some(version) => version
+                .as_integer()
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| ReShaderError::Config("version is not a number".to_string()))?,
+            None => 1,
+        };
+
+        if version > CONFIG_VERSION {
+            return Err(ReShaderError::Config(format!(
+                "version {version} was written by a newer version of ReShader"
+            )));
+        }
+        if version == CONFIG_VERSION {
+            let config =
+                Config::deserialize(value).map_err(|e| ReShaderError::Config(e.to_string()))?;
+            return Ok((config, None));
+        }
+
+        let config = Self::migrate(value)?;
+        let backup = path.with_extension(format!("v{version}.toml.bak"));
+        std::fs::copy(path, &backup)?;
+        config.save(path)?;
+        Ok((config, Some(backup)))
+    }
+
+    fn migrate(value: toml::Value) -> ReShaderResult<Self> {
+        let old = ConfigV1::deserialize(value).map_err(|e| ReShaderError::Config(e.to_string()))?;
+        let mut config = Config::default();
+        for game_path in &old.game_paths {
+            let game = config.register_game(Path::new(game_path));
+            if let Some(link_mode) = old.link_modes.get(game_path) {
+                game.link_mode = *link_mode;
+            }
+            // every game installed before had ReShade linked as dxgi.dll
+            game.api = Some(GraphicsApi::Dxgi);
+        }
+        Ok(config)
+    }
+
+    /// Writes the config
+    ///
+    /// The config is written to a temporary file that replaces the old one, so it is never left half-written.
+    pub fn save(&self, path: &Path) -> ReShaderResult<()> {
+        let content =
+            toml::to_string(self).expect("if you see this error, the toml library is broken");
+        write_atomically(path, content.as_bytes())
+    }
+
+    /// Applies the changes another process saved since `base` was loaded
+    ///
+    /// When both changed the same game, the own changes win.
+    pub fn merge(&mut self, base: &Config, theirs: Config) {
+        let mut games = Vec::new();
+        for game in &theirs.games {
+            match (self.game(&game.path), base.game(&game.path)) {
+                // removed by this process
+                (None, Some(_)) => {}
+                (Some(ours), Some(before)) if ours == before => games.push(game.clone()),
+                (Some(ours), _) => games.push(ours.clone()),
+                // added by the other process
+                (None, None) => games.push(game.clone()),
+            }
+        }
+        for game in &self.games {
+            // added or changed by this process, but removed by the other one
+            if theirs.game(&game.path).is_none() && base.game(&game.path) != Some(game) {
+                games.push(game.clone());
+            }
+        }
+        self.games = games;
+    }
+
+    /// Returns the directories of all games
+    pub fn game_paths(&self) -> Vec<PathBuf> {
+        self.games.iter().map(|game| game.path.clone()).collect()
+    }
+
+    /// Returns the entry of a game
+    pub fn game(&self, game_path: &Path) -> Option<&GameEntry> {
+        self.games.iter().find(|game| game.path == game_path)
+    }
+
+    /// Returns the entry of a game for changing it
+    pub fn game_mut(&mut self, game_path: &Path) -> Option<&mut GameEntry> {
+        self.games.iter_mut().find(|game| game.path == game_path)
+    }
+
+    /// Resolves a game directory given by the user to the directory ReShade is installed to
+    ///
+    /// The path is canonicalized first. A registered game at `path` is used first, then the registered game
+    /// inside `path` if there is exactly one. Otherwise the directory of the most likely executable inside
+    /// `path` is used, see [`find_executables`], and the executable is returned with it.
+    /// If there is none, `path` is used as it is.
+    ///
+    /// Returns [`ReShaderError::AmbiguousGame`] if more than one registered game is inside `path`.
+    pub fn resolve_game(&self, path: &Path) -> ReShaderResult<(PathBuf, Option<GameExecutable>)> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let canonical_paths = self
+            .games
+            .iter()
+            .map(|game| {
+                game.path
+                    .canonicalize()
+                    .unwrap_or_else(|_| game.path.clone())
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(index) = canonical_paths
+            .iter()
+            .position(|game_path| *game_path == path)
+        {
+            return Ok((self.games[index].path.clone(), None));
+        }
+        let mut below = canonical_paths
+            .iter()
+            .enumerate()
+            .filter(|(_, game_path)| game_path.starts_with(&path));
+        match (below.next(), below.next()) {
+            (Some((index, _)), None) => return Ok((self.games[index].path.clone(), None)),
+            (Some(_), Some(_)) => {
+                return Err(ReShaderError::AmbiguousGame(
+                    path.to_string_lossy().to_string(),
+                ))
+            }
+            (None, _) => {}
+        }
+
+        if path.is_file() {
+            let directory = path.parent().unwrap_or(&path);
+            return Ok((directory.to_path_buf(), None));
+        }
+
+        match find_executables(&path)
+            .ok()
+            .and_then(|executables| executables.into_iter().next())
+        {
+            Some(executable) => Ok((executable.directory().to_path_buf(), Some(executable))),
+            None => Ok((path, None)),
+        }
+    }
+
+    /// Returns the entry of a game, adding it with what can be detected from the game directory if needed
+    pub fn register_game(&mut self, game_path: &Path) -> &mut GameEntry {
+        let index = match self.games.iter().position(|game| game.path == game_path) {
+            Some(index) => index,
+            None => {
+                self.games.push(GameEntry::detect(game_path));
+                self.games.len() - 1
+            }
+        };
+        &mut self.games[index]
+    }
+
+    /// Registers a game ReShade was installed for
+    pub fn register_reshade(
+        &mut self,
+        game_path: &Path,
+        vanilla: bool,
+        link_mode: LinkMode,
+    ) -> &mut GameEntry {
+        let game = self.register_game(game_path);
+        game.api = Some(GraphicsApi::Dxgi);
+        game.variant = Some(if vanilla {
+            Variant::Vanilla
+        } else {
+            Variant::Addon
+        });
+        game.link_mode = link_mode;
+        game
+    }
+
+    /// Returns the link mode used for a game before, or the default one
+    pub fn link_mode(&self, game_path: &Path) -> LinkMode {
+        self.game(game_path)
+            .map(|game| game.link_mode)
+            .unwrap_or_default()
+    }
+
+    /// Returns the given link mode or the one used before, remembering it for registered games
+    pub fn use_link_mode(&mut self, game_path: &Path, link_mode: Option<LinkMode>) -> LinkMode {
+        let link_mode = link_mode.unwrap_or_else(|| self.link_mode(game_path));
+        if let Some(game) = self.game_mut(game_path) {
+            game.link_mode = link_mode;
+        }
+        link_mode
+    }
+
+    /// Remembers that shader collections were installed for a registered game
+    pub fn add_collections<S: AsRef<str>>(&mut self, game_path: &Path, collections: &[S]) {
+        if let Some(game) = self.game_mut(game_path) {
+            for collection in collections {
+                let collection = collection.as_ref();
+                if !game.collections.iter().any(|name| name == collection) {
+                    game.collections.push(collection.to_string());
+                }
+            }
+        }
+    }
+
+    /// Forgets a game
+    pub fn remove_game(&mut self, game_path: &Path) {
+        self.games.retain(|game| game.path != game_path);
+    }
+}
+
+impl GameEntry {
+    fn detect(game_path: &Path) -> Self {
+        let executable = find_executables(game_path)
+            .ok()
+            .and_then(|executables| executables.into_iter().next())
+            .filter(|executable| executable.directory() == game_path);
+
+        Self {
+            name: game_name(game_path),
+            path: game_path.to_path_buf(),
+            executable: executable.as_ref().map(|executable| {
+                executable
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            }),
+            architecture: executable.map(|executable| executable.info.architecture),
+            api: None,
+            variant: None,
+            pinned_version: None,
+            link_mode: LinkMode::default(),
+            collections: Vec::new(),
+            launcher: Launcher::detect(game_path),
+        }
+    }
+}
+
+impl Launcher {
+    fn detect(game_path: &Path) -> Option<Self> {
+        let path = game_path.to_string_lossy().to_lowercase();
+        if path.contains("/steamapps/") {
+            Some(Launcher::Steam)
+        } else if path.contains("/.xlcore/") {
+            Some(Launcher::XlCore)
+        } else if path.contains("/heroic/") {
+            Some(Launcher::Heroic)
+        } else if path.contains("/bottles/") {
+            Some(Launcher::Bottles)
+        } else if path.contains("/lutris/") {
+            Some(Launcher::Lutris)
+        } else {
+            None
+        }
+    }
+}
+
+/// Uses the directory below `steamapps/common` for Steam games, the directory name otherwise
+fn game_name(game_path: &Path) -> String {
+    let components = game_path.iter().collect::<Vec<_>>();
+    let name = components
+        .windows(3)
+        .find(|window| window[0] == "steamapps" && window[1] == "common")
+        .map(|window| window[2])
+        .or_else(|| game_path.file_name());
+    name.map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| game_path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn config_with(game_paths: &[&Path]) -> Config {
+        let mut config = Config::default();
+        for game_path in game_paths {
+            config.register_game(game_path);
+        }
+        config
+    }
+
+    #[test]
+    fn v1_configs_are_migrated_and_backed_up() {
+        let tmp = TempDir::new("reshader-config-migrate").unwrap();
+        let path = tmp.path().join("config.toml");
+        let game_path = tmp.path().join("game");
+        let other_path = tmp.path().join("other");
+        let v1 = format!(
+            "game_paths = [\"{}\", \"{}\"]\n\n[link_modes]\n\"{}\" = \"hardlink\"\n",
+            game_path.display(),
+            other_path.display(),
+            game_path.display()
+        );
+        std::fs::write(&path, &v1).unwrap();
+
+        let (config, backup) = Config::load(&path).unwrap();
+
+        let backup = backup.unwrap();
+        assert_eq!(backup, tmp.path().join("config.v1.toml.bak"));
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), v1);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(
+            config.game_paths(),
+            vec![game_path.clone(), other_path.clone()]
+        );
+        assert_eq!(config.link_mode(&game_path), LinkMode::Hardlink);
+        assert_eq!(config.link_mode(&other_path), LinkMode::default());
+        assert_eq!(
+            config.game(&game_path).unwrap().api,
+            Some(GraphicsApi::Dxgi)
+        );
+
+        // the migrated config is saved, so it is only migrated once
+        let (reloaded, backup) = Config::load(&path).unwrap();
+        assert!(backup.is_none());
+        assert_eq!(reloaded.games, config.games);
+    }
+
+    #[test]
+    fn newer_configs_are_rejected() {
+        let tmp = TempDir::new("reshader-config-newer").unwrap();
+        let path = tmp.path().join("config.toml");
+        std::fs::write(&path, format!("version = {}\n", CONFIG_VERSION + 1)).unwrap();
+
+        assert!(matches!(Config::load(&path), Err(ReShaderError::Config(_))));
+    }
+
+    #[test]
+    fn merge_keeps_games_both_processes_added() {
+        let existing = Path::new("/games/existing");
+        let ours_added = Path::new("/games/ours");
+        let theirs_added = Path::new("/games/theirs");
+        let removed = Path::new("/games/removed");
+        let base = config_with(&[existing, removed]);
+
+        let mut ours = base.clone();
+        ours.register_game(ours_added);
+        ours.use_link_mode(existing, Some(LinkMode::Copy));
+        let mut theirs = base.clone();
+        theirs.register_game(theirs_added);
+        theirs.remove_game(removed);
+
+        ours.merge(&base, theirs);
+
+        assert_eq!(
+            ours.game_paths(),
+            vec![
+                existing.to_path_buf(),
+                theirs_added.to_path_buf(),
+                ours_added.to_path_buf()
+            ]
+        );
+        assert_eq!(ours.link_mode(existing), LinkMode::Copy);
+    }
+
+    #[test]
+    fn resolve_game_finds_registered_games() {
+        let tmp = TempDir::new("reshader-config-resolve").unwrap();
+        let library = tmp.path().join("library");
+        let first = library.join("first");
+        let second = library.join("second");
+        std::fs::create_dir_all(first.join("bin")).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::os::unix::fs::symlink(&first, tmp.path().join("link")).unwrap();
+        let config = config_with(&[&first.join("bin"), &second]);
+
+        assert_eq!(config.resolve_game(&second).unwrap().0, second);
+        // a symlink to the game resolves to the registered path
+        assert_eq!(
+            config
+                .resolve_game(&tmp.path().join("link").join("bin"))
+                .unwrap()
+                .0,
+            first.join("bin")
+        );
+        // a single registered game below the path is used
+        assert_eq!(config.resolve_game(&first).unwrap().0, first.join("bin"));
+        assert!(matches!(
+            config.resolve_game(&library),
+            Err(ReShaderError::AmbiguousGame(_))
+        ));
+        // unknown directories are used as they are
+        let unknown = tmp.path().join("unknown");
+        std::fs::create_dir_all(&unknown).unwrap();
+        assert_eq!(
+            config.resolve_game(&unknown).unwrap().0,
+            unknown.canonicalize().unwrap()
+        );
+    }
+}
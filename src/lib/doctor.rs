@@ -0,0 +1,458 @@
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    executable::{find_executables, read_pe_info, Architecture},
+    link::LinkMode,
+    plan::{Action, Plan},
+    prelude::*,
+    receipt::Receipt,
+    reshade_ini::{resolve_search_path, ReShadeIni, Section},
+    wine::{missing_dll_overrides, set_dll_overrides, RESHADE_DLLS},
+    LINKED_PATHS,
+};
+
+/// Something that keeps ReShade from working in a game, see [`diagnose`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The game directory doesn't exist anymore
+    MissingGame,
+    /// A symlink in the game directory points to a path that doesn't exist
+    DanglingLink {
+        /// The symlink
+        path: PathBuf,
+        /// Where it points to
+        target: PathBuf,
+    },
+    /// A ReShade DLL is missing from the game directory
+    MissingDll(PathBuf),
+    /// The ReShade DLL was built for another architecture than the game
+    ArchitectureMismatch {
+        /// The main executable of the game
+        executable: PathBuf,
+        /// The architecture of the game
+        game: Architecture,
+        /// The architecture of the ReShade DLL
+        dll: Architecture,
+    },
+    /// A search path in ReShade.ini points to a directory that doesn't exist
+    MissingSearchPath {
+        /// The key of the list, `EffectSearchPaths` or `TextureSearchPaths`
+        key: String,
+        /// The entry, as written in ReShade.ini
+        entry: String,
+        /// Whether the directory belongs to the shaders ReShader installs, so installing them fixes it
+        linked: bool,
+    },
+    /// The Wine prefix of the game doesn't load the ReShade DLLs instead of its own
+    MissingDllOverrides {
+        /// The Wine prefix
+        prefix: PathBuf,
+        /// The DLLs without an override, without their extension
+        dlls: Vec<String>,
+    },
+    /// The ReShade and the GShade shaders are both searched and contain effect files with the same name
+    ConflictingShaders(Vec<String>),
+}
+
+/// The arguments of `install-presets` the user has to fill in, GShade can't be downloaded automatically
+const GSHADE_ARGUMENTS: &str =
+    "--presets <presets zip or folder> --shaders <shaders zip or folder>";
+
+impl Problem {
+    /// Returns whether [`fix_problem`] can fix the problem
+    pub fn is_fixable(&self) -> bool {
+        !matches!(
+            self,
+            Problem::MissingGame
+                | Problem::ArchitectureMismatch { .. }
+                | Problem::MissingSearchPath { linked: true, .. }
+                | Problem::ConflictingShaders(_)
+        )
+    }
+
+    /// Returns what the user can do about the problem
+    pub fn suggestion(&self, game_path: &Path) -> String {
+        let game = game_path.display();
+        match self {
+            Problem::MissingGame => {
+                format!("stop managing it with `reshader uninstall --game \"{game}\"`")
+            }
+            Problem::DanglingLink { path, .. } => match link_name(path) {
+                "dxgi.dll" | "d3dcompiler_47.dll" => {
+                    format!("reinstall ReShade with `reshader install-reshade --game \"{game}\"`")
+                }
+                "reshade-shaders" => format!(
+                    "reinstall the shaders with `reshader install-reshade-shaders --game \"{game}\"`"
+                ),
+                "gshade-presets" | "gshade-shaders" => format!(
+                    "reinstall the GShade presets with `reshader install-presets --game \"{game}\" {GSHADE_ARGUMENTS}` or the TUI"
+                ),
+                _ => format!("remove {}", path.display()),
+            },
+            Problem::MissingDll(_) => {
+                format!("reinstall ReShade with `reshader install-reshade --game \"{game}\"`")
+            }
+            Problem::ArchitectureMismatch { .. } => {
+                "ReShader only installs 64-bit ReShade, use the official ReShade installer for this game"
+                    .to_string()
+            }
+            Problem::MissingSearchPath {
+                entry,
+                linked: true,
+                ..
+            } if entry.to_lowercase().contains("gshade-shaders") => format!(
+                "install the GShade shaders with `reshader install-presets --game \"{game}\" {GSHADE_ARGUMENTS}` or the TUI"
+            ),
+            Problem::MissingSearchPath { linked: true, .. } => format!(
+                "install the shaders with `reshader install-reshade-shaders --game \"{game}\"`"
+            ),
+            Problem::MissingSearchPath { key, entry, .. } => format!(
+                "remove it with `reshader ini remove GENERAL {key} '{entry}' --game \"{game}\"`"
+            ),
+            Problem::MissingDllOverrides { prefix, .. } => format!(
+                "launch the game with `reshader run -- %command%` or run `reshader install-reshade --game \"{game}\" --wine-prefix \"{}\"`",
+                prefix.display()
+            ),
+            Problem::ConflictingShaders(_) => {
+                "convert the GShade presets with `reshader convert-presets` and remove the gshade-shaders search paths"
+                    .to_string()
+            }
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingGame => write!(f, "the game directory doesn't exist"),
+            Problem::DanglingLink { path, target } => write!(
+                f,
+                "{} points to {}, which doesn't exist",
+                path.display(),
+                target.display()
+            ),
+            Problem::MissingDll(path) => write!(f, "{} is missing", path.display()),
+            Problem::ArchitectureMismatch {
+                executable,
+                game,
+                dll,
+            } => write!(
+                f,
+                "{} is {game}, but the ReShade DLL is {dll}",
+                executable.display()
+            ),
+            Problem::MissingSearchPath { key, entry, .. } => {
+                write!(f, "{key} entry {entry} points to a missing directory")
+            }
+            Problem::MissingDllOverrides { prefix, dlls } => write!(
+                f,
+                "the Wine prefix {} has no native override for {}",
+                prefix.display(),
+                dlls.join(", ")
+            ),
+            Problem::ConflictingShaders(effects) => write!(
+                f,
+                "reshade-shaders and gshade-shaders both contain {}",
+                effects.join(", ")
+            ),
+        }
+    }
+}
+
+/// Checks a game directory for the problems that keep ReShade from working
+///
+/// DLL overrides are only checked if the Wine prefix of the game is given or can be found, see
+/// [`crate::wine::find_wine_prefix`].
+pub fn diagnose(
+    data_dir: &Path,
+    game_path: &Path,
+    wine_prefix: Option<&Path>,
+) -> ReShaderResult<Vec<Problem>> {
+    if !game_path.is_dir() {
+        return Ok(vec![Problem::MissingGame]);
+    }
+
+    let receipt = Receipt::load(data_dir, game_path)?;
+    let mut problems = Vec::new();
+
+    let linked = LINKED_PATHS
+        .iter()
+        .map(|name| game_path.join(name))
+        .chain(receipt.entries.iter().map(|entry| entry.path.clone()))
+        .collect::<BTreeSet<_>>();
+    for path in linked {
+        if let Ok(target) = std::fs::read_link(&path) {
+            if !path.exists() {
+                problems.push(Problem::DanglingLink { path, target });
+            }
+        }
+    }
+
+    let dxgi_path = game_path.join("dxgi.dll");
+    for name in ["dxgi.dll", "d3dcompiler_47.dll"] {
+        let path = game_path.join(name);
+        if std::fs::symlink_metadata(&path).is_err() {
+            problems.push(Problem::MissingDll(path));
+        }
+    }
+
+    if dxgi_path.is_file() {
+        let executable = find_executables(game_path)?.into_iter().next();
+        if let (Some(executable), Some(dll)) = (executable, read_pe_info(&dxgi_path)?) {
+            if executable.info.architecture != dll.architecture {
+                problems.push(Problem::ArchitectureMismatch {
+                    executable: executable.path,
+                    game: executable.info.architecture,
+                    dll: dll.architecture,
+                });
+            }
+        }
+    }
+
+    let ini_path = game_path.join("ReShade.ini");
+    if ini_path.exists() {
+        let reshade_ini = ReShadeIni::load(&ini_path)?;
+        let defaults = ReShadeIni::default_config();
+        let gshade_shaders = game_path.join("gshade-shaders");
+        let gshade_installed = std::fs::symlink_metadata(&gshade_shaders).is_ok();
+        for (key, entries, default_entries) in [
+            (
+                "EffectSearchPaths",
+                reshade_ini.effect_search_paths(),
+                defaults.effect_search_paths(),
+            ),
+            (
+                "TextureSearchPaths",
+                reshade_ini.texture_search_paths(),
+                defaults.texture_search_paths(),
+            ),
+        ] {
+            for entry in entries {
+                let Some(search_path) = resolve_search_path(game_path, &entry) else {
+                    continue;
+                };
+                // ReShader adds the gshade-shaders entries to every ReShade.ini, they only matter once GShade is installed
+                let default = default_entries.iter().any(|default| {
+                    resolve_search_path(game_path, default).as_ref() == Some(&search_path)
+                });
+                if default && !gshade_installed && search_path.path.starts_with(&gshade_shaders) {
+                    continue;
+                }
+                if !search_path.path.is_dir() {
+                    let linked = ["reshade-shaders", "gshade-shaders"]
+                        .iter()
+                        .any(|name| search_path.path.starts_with(game_path.join(name)));
+                    problems.push(Problem::MissingSearchPath {
+                        key: key.to_string(),
+                        entry,
+                        linked,
+                    });
+                }
+            }
+        }
+
+        let searched = |directory: &Path| {
+            reshade_ini.effect_search_paths().iter().any(|entry| {
+                resolve_search_path(game_path, entry)
+                    .is_some_and(|search_path| search_path.path.starts_with(directory))
+            })
+        };
+        let reshade_shaders = game_path.join("reshade-shaders");
+        if searched(&reshade_shaders) && searched(&gshade_shaders) {
+            let reshade_effects = effect_names(&reshade_shaders.join("Shaders"))?;
+            let conflicts = effect_names(&gshade_shaders.join("Shaders"))?
+                .intersection(&reshade_effects)
+                .cloned()
+                .collect::<Vec<_>>();
+            if !conflicts.is_empty() {
+                problems.push(Problem::ConflictingShaders(conflicts));
+            }
+        }
+    }
+
+    let wine_prefix = match wine_prefix {
+        Some(prefix) => Some(prefix.to_path_buf()),
+        None => crate::wine::find_wine_prefix(game_path),
+    };
+    if let Some(prefix) = wine_prefix {
+        let executables = executable_names(game_path)?;
+        let executables = executables.iter().map(String::as_str).collect::<Vec<_>>();
+        let dlls = missing_dll_overrides(&prefix, &executables, &RESHADE_DLLS)?;
+        if !dlls.is_empty() {
+            problems.push(Problem::MissingDllOverrides { prefix, dlls });
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Fixes a problem found by [`diagnose`], returning `false` if it can't be fixed automatically
+///
+/// Links are recreated from the data directory if their source still exists and removed otherwise.
+/// Search paths pointing to missing directories are removed from ReShade.ini after a backup is made,
+/// unless they belong to the shaders ReShader installs.
+pub async fn fix_problem(
+    data_dir: &Path,
+    game_path: &Path,
+    problem: &Problem,
+    link_mode: LinkMode,
+) -> ReShaderResult<bool> {
+    match problem {
+        Problem::DanglingLink { path, .. } => {
+            let merged = data_dir.join("Merged");
+            match link_name(path) {
+                "dxgi.dll" | "d3dcompiler_47.dll" => {
+                    crate::repair_reshade(data_dir, game_path, link_mode).await?;
+                }
+                "reshade-shaders" if merged.is_dir() => {
+                    crate::install_reshade_shaders(data_dir, &merged, game_path, link_mode)?;
+                }
+                "gshade-presets" | "gshade-shaders"
+                    if data_dir.join("reshade-presets").is_dir()
+                        && data_dir.join("reshade-shaders").is_dir() =>
+                {
+                    crate::install_preset_for_game(data_dir, game_path, link_mode)?;
+                }
+                _ => {
+                    let mut plan = Plan::new(game_path);
+                    plan.push(Action::Delete { path: path.clone() });
+                    plan.execute(data_dir)?;
+                }
+            }
+        }
+        Problem::MissingDll(_) => {
+            crate::repair_reshade(data_dir, game_path, link_mode).await?;
+        }
+        Problem::MissingSearchPath {
+            key,
+            entry,
+            linked: false,
+        } => {
+            let ini_path = game_path.join("ReShade.ini");
+            let mut reshade_ini = ReShadeIni::load(&ini_path)?;
+            if reshade_ini.remove_from_list(Section::General, key, entry) {
+                let mut plan = Plan::new(game_path);
+                plan.push(Action::Backup {
+                    path: ini_path.clone(),
+                });
+                plan.push(Action::Write {
+                    path: ini_path,
+                    content: reshade_ini.to_string(),
+                });
+                plan.execute(data_dir)?;
+            }
+        }
+        Problem::MissingDllOverrides { prefix, dlls } => {
+            let dlls = dlls.iter().map(String::as_str).collect::<Vec<_>>();
+            set_dll_overrides(prefix, None, &dlls)?;
+        }
+        Problem::MissingGame
+        | Problem::ArchitectureMismatch { .. }
+        | Problem::MissingSearchPath { linked: true, .. }
+        | Problem::ConflictingShaders(_) => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn link_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+/// Returns the file names of the executables directly inside the game directory
+fn executable_names(game_path: &Path) -> ReShaderResult<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(game_path)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if name.to_lowercase().ends_with(".exe") {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+/// Collects the lowercase file names of the effect files inside a directory and its subdirectories
+fn effect_names(directory: &Path) -> ReShaderResult<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+    if !directory.is_dir() {
+        return Ok(names);
+    }
+
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            names.extend(effect_names(&path)?);
+            continue;
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".fx") {
+            names.insert(name);
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn temp_game(root: &Path) -> (PathBuf, PathBuf) {
+        let data_dir = root.join("data");
+        let game_path = root.join("game");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::create_dir_all(game_path.join("reshade-shaders/Shaders")).unwrap();
+        std::fs::create_dir_all(game_path.join("reshade-shaders/Textures")).unwrap();
+        ReShadeIni::default_config()
+            .save(&game_path.join("ReShade.ini"))
+            .unwrap();
+        (data_dir, game_path)
+    }
+
+    fn missing_search_paths(problems: &[Problem]) -> Vec<&str> {
+        problems
+            .iter()
+            .filter_map(|problem| match problem {
+                Problem::MissingSearchPath { entry, .. } => Some(entry.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shipped_default_ini_has_no_missing_search_paths() {
+        let tmp = TempDir::new("reshader-doctor-default").unwrap();
+        let (data_dir, game_path) = temp_game(tmp.path());
+
+        let problems = diagnose(&data_dir, &game_path, None).unwrap();
+
+        assert_eq!(missing_search_paths(&problems), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn gshade_search_paths_are_checked_once_gshade_is_installed() {
+        let tmp = TempDir::new("reshader-doctor-gshade").unwrap();
+        let (data_dir, game_path) = temp_game(tmp.path());
+        let gshade_shaders = game_path.join("gshade-shaders");
+        std::fs::create_dir_all(gshade_shaders.join("Shaders")).unwrap();
+
+        let problems = diagnose(&data_dir, &game_path, None).unwrap();
+
+        assert_eq!(
+            missing_search_paths(&problems),
+            [
+                ".\\gshade-shaders\\ComputeShaders",
+                ".\\gshade-shaders\\Textures"
+            ]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The information ReShader reads from a ReShade effect file (`.fx`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EffectInfo {
+    /// The techniques declared in the effect file
+    pub techniques: Vec<EffectTechnique>,
+    /// The uniform variables declared in the effect file, which presets can change
+    #[serde(default)]
+    pub uniforms: Vec<EffectUniform>,
+    /// The textures declared in the effect file
+    #[serde(default)]
+    pub textures: Vec<EffectTexture>,
+}
+
+/// A technique declared in an effect file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectTechnique {
+    /// The name of the technique, as referenced by presets
+    pub name: String,
+    /// The annotations of the technique, e.g. `ui_label` and `ui_tooltip`
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+}
+
+/// A uniform variable declared in an effect file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectUniform {
+    /// The name of the uniform, as referenced by presets
+    pub name: String,
+    /// The type of the uniform, e.g. `float3`
+    pub type_name: String,
+    /// The default value as written in the effect file
+    pub default: Option<String>,
+    /// The annotations of the uniform, e.g. `ui_label`, `ui_min` and `ui_max`
+    pub annotations: BTreeMap<String, String>,
+}
+
+/// A texture declared in an effect file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectTexture {
+    /// The name of the texture inside the effect
+    pub name: String,
+    /// The annotations of the texture, e.g. `source`
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl EffectTechnique {
+    /// Returns the name shown in the ReShade overlay (`ui_label`)
+    pub fn label(&self) -> Option<&str> {
+        self.annotations.get("ui_label").map(String::as_str)
+    }
+
+    /// Returns the description shown in the ReShade overlay (`ui_tooltip`)
+    pub fn tooltip(&self) -> Option<&str> {
+        self.annotations.get("ui_tooltip").map(String::as_str)
+    }
+}
+
+impl EffectUniform {
+    /// Returns the name shown in the ReShade overlay (`ui_label`)
+    pub fn label(&self) -> Option<&str> {
+        self.annotations.get("ui_label").map(String::as_str)
+    }
+
+    /// Returns the description shown in the ReShade overlay (`ui_tooltip`)
+    pub fn tooltip(&self) -> Option<&str> {
+        self.annotations.get("ui_tooltip").map(String::as_str)
+    }
+}
+
+impl EffectTexture {
+    /// Returns the image file the texture is loaded from, relative to the texture search paths
+    pub fn source(&self) -> Option<&str> {
+        self.annotations.get("source").map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Literal(String),
+    Symbol(char),
+}
+
+impl Token {
+    fn text(&self) -> String {
+        match self {
+            Token::Identifier(text) | Token::Literal(text) => text.clone(),
+            Token::Symbol(symbol) => symbol.to_string(),
+        }
+    }
+}
+
+/// Parses the source of an effect file
+///
+/// This is not a full ReShade FX parser, it only looks for the declarations ReShader needs.
+/// Preprocessor directives are skipped, so declarations behind `#if` are always included.
+pub fn parse_effect(source: &str) -> EffectInfo {
+    let tokens = tokenize(source);
+    let mut info = EffectInfo::default();
+
+    let mut index = 0;
+    while index < tokens.len() {
+        match &tokens[index] {
+            Token::Identifier(keyword) if keyword == "technique" => {
+                if let Some(Token::Identifier(name)) = tokens.get(index + 1) {
+                    let (annotations, next) = parse_annotations(&tokens, index + 2);
+                    info.techniques.push(EffectTechnique {
+                        name: name.clone(),
+                        annotations,
+                    });
+                    index = next;
+                    continue;
+                }
+            }
+            Token::Identifier(keyword) if keyword == "uniform" => {
+                if let Some((uniform, next)) = parse_uniform(&tokens, index + 1) {
+                    info.uniforms.push(uniform);
+                    index = next;
+                    continue;
+                }
+            }
+            Token::Identifier(keyword) if keyword == "texture" => {
+                if let Some(Token::Identifier(name)) = tokens.get(index + 1) {
+                    let mut next = index + 2;
+                    // skip semantics like `: COLOR`
+                    if tokens.get(next) == Some(&Token::Symbol(':')) {
+                        next += 2;
+                    }
+                    let (annotations, next) = parse_annotations(&tokens, next);
+                    info.textures.push(EffectTexture {
+                        name: name.clone(),
+                        annotations,
+                    });
+                    index = next;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+
+    info
+}
+
+/// Parses `type name[size] <annotations> = default;` following the `uniform` keyword
+fn parse_uniform(tokens: &[Token], start: usize) -> Option<(EffectUniform, usize)> {
+    let mut index = start;
+    let mut words = Vec::new();
+    while let Some(Token::Identifier(word)) = tokens.get(index) {
+        words.push(word.clone());
+        index += 1;
+    }
+    if words.len() < 2 {
+        return None;
+    }
+    let name = words.pop().unwrap();
+
+    if tokens.get(index) == Some(&Token::Symbol('[')) {
+        while tokens
+            .get(index)
+            .is_some_and(|token| *token != Token::Symbol(']'))
+        {
+            index += 1;
+        }
+        index += 1;
+    }
+
+    let (annotations, mut index) = parse_annotations(tokens, index);
+
+    let mut default = None;
+    if tokens.get(index) == Some(&Token::Symbol('=')) {
+        let mut value = String::new();
+        index += 1;
+        while let Some(token) = tokens.get(index) {
+            if *token == Token::Symbol(';') {
+                break;
+            }
+            value.push_str(&token.text());
+            index += 1;
+        }
+        default = Some(value);
+    }
+
+    Some((
+        EffectUniform {
+            name,
+            type_name: words.join(" "),
+            default,
+            annotations,
+        },
+        index,
+    ))
+}
+
+/// Parses `< type key = value; ... >` and returns the annotations and the index after them
+fn parse_annotations(tokens: &[Token], start: usize) -> (BTreeMap<String, String>, usize) {
+    let mut annotations = BTreeMap::new();
+    if tokens.get(start) != Some(&Token::Symbol('<')) {
+        return (annotations, start);
+    }
+
+    let mut index = start + 1;
+    let mut statement = Vec::new();
+    while let Some(token) = tokens.get(index) {
+        index += 1;
+        match token {
+            Token::Symbol(';') | Token::Symbol('>') => {
+                if let Some(equals) = statement.iter().position(|t| *t == Token::Symbol('=')) {
+                    let key = statement[..equals].iter().rev().find_map(|t| match t {
+                        Token::Identifier(key) => Some(key.clone()),
+                        _ => None,
+                    });
+                    if let Some(key) = key {
+                        let value = statement[equals + 1..]
+                            .iter()
+                            .map(Token::text)
+                            .collect::<String>();
+                        annotations.insert(key, value);
+                    }
+                }
+                statement.clear();
+
+                if *token == Token::Symbol('>') {
+                    break;
+                }
+            }
+            token => statement.push(token.clone()),
+        }
+    }
+
+    (annotations, index)
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '#' if line_start => {
+                // skip preprocessor directives, including lines continued with a backslash
+                let mut continued = false;
+                for next in chars.by_ref() {
+                    match next {
+                        '\n' if !continued => break,
+                        '\\' => continued = true,
+                        c if !c.is_whitespace() => continued = false,
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+                line_start = true;
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            '"' => {
+                let mut literal = String::new();
+                let mut escaped = false;
+                for next in chars.by_ref() {
+                    if escaped {
+                        literal.push(match next {
+                            'n' => '\n',
+                            't' => '\t',
+                            other => other,
+                        });
+                        escaped = false;
+                    } else if next == '\\' {
+                        escaped = true;
+                    } else if next == '"' {
+                        break;
+                    } else {
+                        literal.push(next);
+                    }
+                }
+                // adjacent string literals are concatenated, like in C
+                if let Some(Token::Literal(previous)) = tokens.last_mut() {
+                    previous.push_str(&literal);
+                } else {
+                    tokens.push(Token::Literal(literal));
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || next == '_' || next == '.' {
+                        word.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Identifier(word));
+            }
+            c => tokens.push(Token::Symbol(c)),
+        }
+        line_start = false;
+    }
+
+    tokens
+}
@@ -0,0 +1,326 @@
+use std::{
+    fmt::{Display, Formatter},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// How deep [`find_executables`] descends into a game directory
+const MAX_SCAN_DEPTH: usize = 6;
+
+/// Directories that never contain the main executable of a game
+const IGNORED_DIRECTORIES: [&str; 12] = [
+    "reshade-shaders",
+    "reshade-presets",
+    "gshade-shaders",
+    "gshade-presets",
+    "_commonredist",
+    "__installer",
+    "redist",
+    "redistributables",
+    "directx",
+    "vcredist",
+    "easyanticheat",
+    "battleye",
+];
+
+/// Name fragments of executables that are not the game itself
+const UNLIKELY_NAMES: [&str; 16] = [
+    "launcher",
+    "setup",
+    "unins",
+    "crash",
+    "report",
+    "redist",
+    "dxsetup",
+    "helper",
+    "update",
+    "patcher",
+    "config",
+    "settings",
+    "benchmark",
+    "server",
+    "easyanticheat",
+    "battleye",
+];
+
+/// Name fragments of executables that are likely the game itself
+const LIKELY_NAMES: [&str; 6] = ["shipping", "win64", "dx11", "dx12", "game", "x64"];
+
+/// The CPU architecture a Windows executable was built for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Architecture {
+    /// 32-bit x86
+    X86,
+    /// 64-bit x86
+    X64,
+    /// 64-bit ARM
+    Arm64,
+}
+
+impl Display for Architecture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Architecture::X86 => write!(f, "x86"),
+            Architecture::X64 => write!(f, "x64"),
+            Architecture::Arm64 => write!(f, "arm64"),
+        }
+    }
+}
+
+/// Information read from the headers of a PE (Windows) executable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeInfo {
+    /// The architecture the executable was built for
+    pub architecture: Architecture,
+    /// Whether the file is a DLL instead of an executable
+    pub dll: bool,
+    /// Whether the executable runs in a console instead of a window
+    pub console: bool,
+}
+
+/// A possible main executable of a game, found by [`find_executables`]
+#[derive(Debug, Clone)]
+pub struct GameExecutable {
+    /// The path to the executable
+    pub path: PathBuf,
+    /// The size of the executable in bytes
+    pub size: u64,
+    /// The headers of the executable
+    pub info: PeInfo,
+    /// How likely this is the main executable of the game, higher is more likely
+    pub score: i64,
+}
+
+impl GameExecutable {
+    /// Returns the directory containing the executable, which is where ReShade has to be installed
+    pub fn directory(&self) -> &Path {
+        self.path.parent().unwrap_or(&self.path)
+    }
+}
+
+impl Display for GameExecutable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}, {:.1} MB)",
+            self.path.display(),
+            self.info.architecture,
+            self.size as f64 / 1_000_000.0
+        )
+    }
+}
+
+/// Reads the PE headers of a file
+///
+/// Returns `None` if the file is not a PE file or has an unknown architecture.
+pub fn read_pe_info(path: &Path) -> ReShaderResult<Option<PeInfo>> {
+    let mut file = std::fs::File::open(path)?;
+
+    let mut dos_header = [0u8; 64];
+    if file.read_exact(&mut dos_header).is_err() || &dos_header[..2] != b"MZ" {
+        return Ok(None);
+    }
+    let pe_offset = u32::from_le_bytes(dos_header[0x3c..0x40].try_into().unwrap()) as u64;
+
+    // signature (4 bytes), COFF header (20 bytes) and the optional header up to the subsystem
+    let mut headers = [0u8; 24 + 70];
+    file.seek(SeekFrom::Start(pe_offset))?;
+    if file.read_exact(&mut headers).is_err() || &headers[..4] != b"PE\0\0" {
+        return Ok(None);
+    }
+
+    let machine = u16::from_le_bytes([headers[4], headers[5]]);
+    let characteristics = u16::from_le_bytes([headers[22], headers[23]]);
+    let subsystem = u16::from_le_bytes([headers[24 + 68], headers[24 + 69]]);
+
+    let architecture = match machine {
+        0x014c => Architecture::X86,
+        0x8664 => Architecture::X64,
+        0xaa64 => Architecture::Arm64,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(PeInfo {
+        architecture,
+        dll: characteristics & 0x2000 != 0,
+        console: subsystem == 3,
+    }))
+}
+
+/// Reads the file version (e.g. `5.9.2.1`) from the version resource of a PE file
+///
+/// Returns `None` if the file has no version resource.
+pub fn read_file_version(path: &Path) -> ReShaderResult<Option<String>> {
+    let content = std::fs::read(path)?;
+    // the fixed part of the version resource starts with this signature, followed by the
+    // structure version and the file version as two 32-bit numbers
+    let Some(offset) = content
+        .windows(4)
+        .position(|window| window == [0xbd, 0x04, 0xef, 0xfe])
+    else {
+        return Ok(None);
+    };
+    let Some(fixed) = content.get(offset + 8..offset + 16) else {
+        return Ok(None);
+    };
+
+    let most = u32::from_le_bytes(fixed[..4].try_into().unwrap());
+    let least = u32::from_le_bytes(fixed[4..].try_into().unwrap());
+    Ok(Some(format!(
+        "{}.{}.{}.{}",
+        most >> 16,
+        most & 0xffff,
+        least >> 16,
+        least & 0xffff
+    )))
+}
+
+/// Scans a game directory for executables and ranks them by how likely they are the main executable
+///
+/// The ranking takes the size, the name, the architecture and launcher metadata
+/// (e.g. GOG's `goggame-*.info` files) into account. The most likely executable comes first.
+/// Subdirectories and files that can't be read are skipped.
+pub fn find_executables(game_path: &Path) -> ReShaderResult<Vec<GameExecutable>> {
+    let mut executables = Vec::new();
+    collect_executables(game_path, 0, &mut executables)?;
+
+    let primary = launcher_executables(game_path);
+    let folder_name = game_path
+        .file_name()
+        .map(|name| normalize_name(&name.to_string_lossy()))
+        .unwrap_or_default();
+
+    for executable in &mut executables {
+        executable.score = score_executable(executable, &folder_name, &primary);
+    }
+    executables.sort_by(|a, b| b.score.cmp(&a.score).then(b.size.cmp(&a.size)));
+
+    Ok(executables)
+}
+
+fn collect_executables(
+    directory: &Path,
+    depth: usize,
+    executables: &mut Vec<GameExecutable>,
+) -> ReShaderResult<()> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        // only the game directory itself has to be readable, other directories are skipped
+        Err(_) if depth > 0 => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+
+        if file_type.is_dir() {
+            if depth < MAX_SCAN_DEPTH && !IGNORED_DIRECTORIES.contains(&name.as_str()) {
+                collect_executables(&path, depth + 1, executables)?;
+            }
+            continue;
+        }
+
+        if !file_type.is_file() || !name.ends_with(".exe") {
+            continue;
+        }
+
+        let (Ok(Some(info)), Ok(metadata)) = (read_pe_info(&path), entry.metadata()) else {
+            continue;
+        };
+        if info.dll {
+            continue;
+        }
+        executables.push(GameExecutable {
+            size: metadata.len(),
+            path,
+            info,
+            score: 0,
+        });
+    }
+
+    Ok(())
+}
+
+fn score_executable(executable: &GameExecutable, folder_name: &str, primary: &[PathBuf]) -> i64 {
+    let name = executable
+        .path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    // bigger executables are more likely to contain the game, capped so size alone can't win
+    let mut score = ((executable.size / 1_000_000) as i64).min(100);
+
+    if primary.iter().any(|path| path == &executable.path) {
+        score += 1000;
+    }
+    let normalized = normalize_name(&name);
+    if !folder_name.is_empty()
+        && (normalized.contains(folder_name)
+            || (normalized.len() >= 4 && folder_name.contains(&normalized)))
+    {
+        score += 100;
+    }
+    if LIKELY_NAMES.iter().any(|likely| name.contains(likely)) {
+        score += 50;
+    }
+    if UNLIKELY_NAMES
+        .iter()
+        .any(|unlikely| name.contains(unlikely))
+    {
+        score -= 200;
+    }
+    if executable.info.console {
+        score -= 50;
+    }
+    if executable.info.architecture != Architecture::X64 {
+        // ReShader only installs the 64-bit version of ReShade
+        score -= 25;
+    }
+
+    score
+}
+
+/// Reads the primary executables from launcher metadata in the game directory
+fn launcher_executables(game_path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(game_path) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            name.starts_with("goggame-") && name.ends_with(".info")
+        })
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .filter_map(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .flat_map(|info| {
+            info["playTasks"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|task| task["isPrimary"].as_bool().unwrap_or(false))
+                .filter_map(|task| task["path"].as_str().map(|path| path.replace('\\', "/")))
+                .map(|path| game_path.join(path))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
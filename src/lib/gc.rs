@@ -0,0 +1,171 @@
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::GameEntry,
+    prelude::*,
+    receipt::Receipt,
+    reshade_version_dir,
+    transaction::{Transaction, JOURNAL_DIR},
+    LINKED_PATHS, VERSIONS_DIR,
+};
+
+/// The files in the data directory that games link to
+const LINKABLE_PATHS: [&str; 6] = [
+    "ReShade64.Addon.dll",
+    "ReShade64.Vanilla.dll",
+    "d3dcompiler_47.dll",
+    "Merged",
+    "reshade-presets",
+    "reshade-shaders",
+];
+
+/// Something in the data directory that no registered game needs, see [`find_artifacts`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    /// The file or directory
+    pub path: PathBuf,
+    /// What it is
+    pub kind: ArtifactKind,
+    /// The space it takes up in bytes
+    pub size: u64,
+}
+
+/// What an [`Artifact`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    /// A ReShade version no game is pinned to
+    ReShadeVersion,
+    /// A ReShade DLL or `d3dcompiler_47.dll` no game links to
+    ReShadeDll,
+    /// The merged shader collections, which no game links to
+    ShaderCollections,
+    /// GShade presets or shaders no game links to
+    GShade,
+    /// Downloaded shader collections or archives
+    Download,
+    /// Files left behind by an interrupted operation
+    Leftover,
+}
+
+impl Display for ArtifactKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArtifactKind::ReShadeVersion => write!(f, "unused ReShade version"),
+            ArtifactKind::ReShadeDll => write!(f, "unused ReShade DLL"),
+            ArtifactKind::ShaderCollections => write!(f, "unused shader collections"),
+            ArtifactKind::GShade => write!(f, "unused GShade files"),
+            ArtifactKind::Download => write!(f, "cached download"),
+            ArtifactKind::Leftover => write!(f, "leftover"),
+        }
+    }
+}
+
+/// Finds the files and directories in the data directory that none of the games need
+///
+/// A path is needed if a game links to it or was copied from it, or if a game is pinned to the ReShade version it contains.
+/// The receipts of all games are checked, not only the ones of `games`.
+/// Receipts, presets, indexes and catalogs are never reported.
+pub fn find_artifacts(data_dir: &Path, games: &[GameEntry]) -> ReShaderResult<Vec<Artifact>> {
+    // the receipts of games another process is installing right now are written before the config
+    let mut sources = Receipt::load_all(data_dir)?
+        .into_iter()
+        .flat_map(|receipt| receipt.entries)
+        .filter_map(|entry| entry.source)
+        .collect::<Vec<_>>();
+    for game in games {
+        // games installed before receipts existed only have the known symlinks
+        for name in LINKED_PATHS {
+            if let Ok(target) = std::fs::read_link(game.path.join(name)) {
+                sources.push(game.path.join(target));
+            }
+        }
+        if let Some(version) = &game.pinned_version {
+            sources.push(reshade_version_dir(data_dir, version));
+        }
+    }
+    let sources = sources
+        .into_iter()
+        .map(|source| source.canonicalize().unwrap_or(source))
+        .collect::<Vec<_>>();
+    let needed = |path: &Path| {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        sources.iter().any(|source| source.starts_with(&path))
+    };
+
+    let mut artifacts = Vec::new();
+    let mut push = |path: PathBuf, kind: ArtifactKind| -> ReShaderResult<()> {
+        let size = disk_size(&path)?;
+        artifacts.push(Artifact { path, kind, size });
+        Ok(())
+    };
+
+    let versions = data_dir.join(VERSIONS_DIR);
+    if versions.is_dir() {
+        for entry in std::fs::read_dir(versions)? {
+            let path = entry?.path();
+            if !needed(&path) {
+                push(path, ArtifactKind::ReShadeVersion)?;
+            }
+        }
+    }
+
+    for name in LINKABLE_PATHS {
+        let path = data_dir.join(name);
+        if std::fs::symlink_metadata(&path).is_err() || needed(&path) {
+            continue;
+        }
+        let kind = match name {
+            "Merged" => ArtifactKind::ShaderCollections,
+            "reshade-presets" | "reshade-shaders" => ArtifactKind::GShade,
+            _ => ArtifactKind::ReShadeDll,
+        };
+        push(path, kind)?;
+    }
+
+    // a journal means an interrupted operation still has to be rolled back, which needs its files
+    let journal = data_dir.join(JOURNAL_DIR);
+    let interrupted = journal.is_dir() && std::fs::read_dir(journal)?.next().is_some();
+    for entry in std::fs::read_dir(data_dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name == "zips" || name.ends_with(".zip") {
+            push(path, ArtifactKind::Download)?;
+        } else if !interrupted
+            && (name.contains(".reshader-new") || name.contains(".reshader-undo-"))
+        {
+            push(path, ArtifactKind::Leftover)?;
+        }
+    }
+
+    Ok(artifacts)
+}
+
+/// Removes artifacts found by [`find_artifacts`], returning the space that was freed in bytes
+///
+/// If one can't be removed, the others are restored, see [`Transaction`].
+pub fn remove_artifacts(data_dir: &Path, artifacts: &[Artifact]) -> ReShaderResult<u64> {
+    Transaction::run(data_dir, |transaction| {
+        for artifact in artifacts {
+            transaction.stage(&artifact.path)?;
+        }
+        Ok(())
+    })?;
+    Ok(artifacts.iter().map(|artifact| artifact.size).sum())
+}
+
+/// Returns the size of a file, or of all files in a directory, without following symlinks
+fn disk_size(path: &Path) -> ReShaderResult<u64> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        size += disk_size(&entry?.path())?;
+    }
+    Ok(size)
+}
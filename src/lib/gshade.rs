@@ -0,0 +1,751 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, SystemTime},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    catalog::ShaderCatalog,
+    collection_index::CollectionIndex,
+    prelude::*,
+    preset::{is_preset, Preset, Technique},
+    reshade_ini::backup,
+};
+
+static DEFAULT_MAPPING: &str = include_str!("../../data/gshade-mapping.toml");
+
+/// The subfolder of the GShade presets installed by default, GShade was made for FFXIV
+pub const DEFAULT_PRESETS_SUBFOLDER: &str = "FFXIV";
+
+/// Which ReShade effect files and techniques replace the GShade ones
+///
+/// The table shipped with ReShader lives in `data/gshade-mapping.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GShadeMapping {
+    /// GShade effect files and the ReShade effect files replacing them
+    #[serde(default)]
+    pub effects: BTreeMap<String, String>,
+    /// GShade techniques (`Name@File.fx`) and the ReShade techniques replacing them
+    #[serde(default)]
+    pub techniques: BTreeMap<String, String>,
+    /// GShade effect files without a replacement and the reason why
+    #[serde(default)]
+    pub unsupported: BTreeMap<String, String>,
+    /// GShade effect files and how their uniforms are called in the ReShade effect file replacing them
+    #[serde(default)]
+    pub uniforms: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// What [`convert_preset`] changed and what it couldn't convert
+#[derive(Debug, Clone, Default)]
+pub struct ConversionReport {
+    /// Techniques and effect files that were replaced, as pairs of old and new name
+    pub replaced: Vec<(String, String)>,
+    /// Effect files the mapping table marks as unsupported, with the reason
+    pub unsupported: Vec<(String, String)>,
+    /// Uniform values of replaced effect files that the mapping table doesn't map and that the new effect file
+    /// doesn't declare, as pairs of the new effect file and the uniform. Without a shader catalog to check the
+    /// new effect file against, every uniform that isn't mapped is listed.
+    pub unmapped: Vec<(String, String)>,
+    /// Techniques no indexed shader collection provides after the conversion
+    pub unknown: Vec<Technique>,
+    /// Whether the conversion changed the preset
+    pub changed: bool,
+}
+
+impl GShadeMapping {
+    /// Returns the mapping table shipped with ReShader
+    pub fn builtin() -> Self {
+        Self::parse(DEFAULT_MAPPING).expect("the builtin GShade mapping table is invalid")
+    }
+
+    /// Parses a mapping table in the format of `data/gshade-mapping.toml`
+    pub fn parse(content: &str) -> ReShaderResult<Self> {
+        toml::from_str(content).map_err(|e| ReShaderError::GShadeMapping(e.to_string()))
+    }
+
+    /// Reads a mapping table
+    pub fn load(path: &Path) -> ReShaderResult<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Returns the ReShade effect file replacing a GShade effect file
+    pub fn map_effect(&self, file: &str) -> Option<&str> {
+        lookup(&self.effects, file)
+    }
+
+    /// Returns the ReShade uniform replacing a uniform of a GShade effect file
+    pub fn map_uniform(&self, file: &str, uniform: &str) -> Option<&str> {
+        let (_, uniforms) = self
+            .uniforms
+            .iter()
+            .find(|(from, _)| from.eq_ignore_ascii_case(file))?;
+        lookup(uniforms, uniform)
+    }
+
+    /// Returns the ReShade technique replacing a GShade technique
+    pub fn map_technique(&self, technique: &Technique) -> Option<Technique> {
+        if let Some(mapped) = lookup(&self.techniques, &technique.to_string()) {
+            return Some(mapped.parse().unwrap());
+        }
+
+        let file = self.map_effect(technique.file.as_deref()?)?;
+        Some(Technique::new(&technique.name, Some(file)))
+    }
+}
+
+/// Finds a key ignoring case, as effect files are matched on Windows
+fn lookup<'a>(table: &'a BTreeMap<String, String>, key: &str) -> Option<&'a str> {
+    table
+        .iter()
+        .find(|(from, _)| from.eq_ignore_ascii_case(key))
+        .map(|(_, to)| to.as_str())
+}
+
+/// Rewrites a GShade preset to use the effects of the ReShade shader collections
+///
+/// Techniques that are replaced keep their shortcut and their uniform values, uniforms are renamed as the
+/// mapping table says. If a collection index is given, techniques none of the collections provide are reported.
+/// If a shader catalog is given, uniforms are checked against the effect files replacing the GShade ones.
+pub fn convert_preset(
+    preset: &mut Preset,
+    mapping: &GShadeMapping,
+    index: Option<&CollectionIndex>,
+    catalog: Option<&ShaderCatalog>,
+) -> ConversionReport {
+    let mut report = ConversionReport::default();
+    let before = preset.to_string();
+
+    let convert_list = |techniques: Vec<Technique>, report: &mut ConversionReport| {
+        techniques
+            .into_iter()
+            .map(|technique| match mapping.map_technique(&technique) {
+                Some(mapped) => {
+                    let replaced = (technique.to_string(), mapped.to_string());
+                    if !report.replaced.contains(&replaced) {
+                        report.replaced.push(replaced);
+                    }
+                    mapped
+                }
+                None => technique,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let original = preset.techniques();
+    let techniques = convert_list(original.clone(), &mut report);
+    let sorting = convert_list(preset.technique_sorting(), &mut report);
+    preset.set_techniques(&techniques);
+    if !sorting.is_empty() {
+        preset.set_technique_sorting(&sorting);
+    }
+
+    for (old, new) in original.iter().zip(&techniques) {
+        if old.name != new.name {
+            if let Some(binding) = preset.technique_key(&old.name) {
+                preset
+                    .document_mut()
+                    .remove(None, &format!("Key{}", old.name));
+                preset.set_technique_key(&new.name, binding);
+            }
+        }
+    }
+
+    let effect_files = preset
+        .effect_files()
+        .into_iter()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    for file in effect_files {
+        if let Some(mapped) = mapping.map_effect(&file) {
+            let uniforms = preset
+                .document()
+                .section(Some(&file))
+                .map(|section| {
+                    section
+                        .entries()
+                        .map(|(uniform, _)| uniform.to_string())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            let declared = catalog
+                .and_then(|catalog| catalog.effect(mapped))
+                .map(|effect| &effect.info.uniforms);
+            for uniform in uniforms {
+                if let Some(new_uniform) = mapping.map_uniform(&file, &uniform) {
+                    preset
+                        .document_mut()
+                        .rename_key(Some(&file), &uniform, new_uniform);
+                    continue;
+                }
+                let exists =
+                    declared.is_some_and(|declared| declared.iter().any(|u| u.name == uniform));
+                if !exists {
+                    report.unmapped.push((mapped.to_string(), uniform));
+                }
+            }
+
+            preset.document_mut().rename_section(&file, mapped);
+            let replaced = (file, mapped.to_string());
+            if !report.replaced.contains(&replaced) {
+                report.replaced.push(replaced);
+            }
+        }
+    }
+
+    for technique in &techniques {
+        let Some(file) = &technique.file else {
+            continue;
+        };
+        if let Some(reason) = lookup(&mapping.unsupported, file) {
+            if !report.unsupported.iter().any(|(f, _)| f == file) {
+                report.unsupported.push((file.clone(), reason.to_string()));
+            }
+        }
+    }
+
+    if let Some(index) = index {
+        report.unknown = techniques
+            .iter()
+            .filter(|technique| {
+                let unsupported = technique
+                    .file
+                    .as_deref()
+                    .is_some_and(|file| lookup(&mapping.unsupported, file).is_some());
+                !unsupported
+                    && !index
+                        .collections
+                        .iter()
+                        .any(|collection| collection.provides(technique))
+            })
+            .cloned()
+            .collect();
+    }
+
+    report.changed = preset.to_string() != before;
+    report
+}
+
+/// Converts every preset in the given files and directories, see [`convert_preset`]
+///
+/// Presets are rewritten in place after a backup is made, or written to the output
+/// directory if there is one. Presets that don't change aren't rewritten in place.
+/// Returns the report for every preset, with [`ConversionReport::changed`] set for the ones that changed.
+pub fn convert_presets(
+    paths: &[PathBuf],
+    output: Option<&Path>,
+    mapping: &GShadeMapping,
+    index: Option<&CollectionIndex>,
+    catalog: Option<&ShaderCatalog>,
+) -> ReShaderResult<Vec<(PathBuf, ConversionReport)>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            list_ini_files(path, path, &mut files)?;
+        } else {
+            let file_name = path.file_name().unwrap_or_default();
+            files.push((path.clone(), PathBuf::from(file_name)));
+        }
+    }
+
+    let mut reports = Vec::new();
+    for (path, relative) in files {
+        let content = std::fs::read_to_string(&path)?;
+        if !is_preset(&content) {
+            continue;
+        }
+
+        let mut preset = Preset::parse(&content);
+        let report = convert_preset(&mut preset, mapping, index, catalog);
+
+        let target = match output {
+            Some(output) => output.join(relative),
+            None if !report.changed => {
+                reports.push((path, report));
+                continue;
+            }
+            None => {
+                backup(&path)?;
+                path
+            }
+        };
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        preset.save(&target)?;
+        reports.push((target, report));
+    }
+
+    Ok(reports)
+}
+
+fn list_ini_files(
+    root: &Path,
+    directory: &Path,
+    files: &mut Vec<(PathBuf, PathBuf)>,
+) -> ReShaderResult<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            list_ini_files(root, &path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("ini"))
+        {
+            let relative = path.strip_prefix(root).unwrap().to_path_buf();
+            files.push((path, relative));
+        }
+    }
+
+    Ok(())
+}
+
+/// A zip file or folder containing GShade presets or shaders
+///
+/// This can be a zip downloaded from GitLab, an extracted folder, a git checkout or a folder
+/// of an existing GShade installation like `gshade-shaders`. Where the presets and shaders are
+/// inside it is detected from its structure, not from its name.
+#[derive(Debug)]
+pub struct GShadeSource {
+    path: PathBuf,
+    archive: Option<RefCell<zip::ZipArchive<std::fs::File>>>,
+    /// The files of the source, relative to its root
+    files: Vec<PathBuf>,
+}
+
+impl GShadeSource {
+    /// Opens a zip file or folder
+    pub fn open(path: &Path) -> ReShaderResult<Self> {
+        if path.is_dir() {
+            let mut files = Vec::new();
+            list_source_files(path, path, &mut files)?;
+            files.sort();
+            return Ok(Self {
+                path: path.to_path_buf(),
+                archive: None,
+                files,
+            });
+        }
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)
+            .map_err(|_| ReShaderError::ReadZipFile)?;
+        let mut files = Vec::new();
+        for i in 0..archive.len() {
+            let entry = archive.by_index(i)?;
+            // entries with `..` or an absolute path would end up outside of the target directory
+            let Some(name) = entry.enclosed_name().map(Path::to_path_buf) else {
+                continue;
+            };
+            if !entry.is_dir() && !is_git_path(&name) {
+                files.push(name);
+            }
+        }
+        files.sort();
+        Ok(Self {
+            path: path.to_path_buf(),
+            archive: Some(RefCell::new(archive)),
+            files,
+        })
+    }
+
+    /// Returns the location of the zip file or folder
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Finds the folder containing the presets
+    ///
+    /// The GShade presets repository contains presets for several games, a subfolder like `FFXIV`
+    /// selects the presets of one of them, see [`Self::preset_subfolders`]. Without one, the folder
+    /// containing all presets is used.
+    pub fn presets_root(&self, subfolder: Option<&str>) -> ReShaderResult<Option<PathBuf>> {
+        let presets = self.preset_files()?;
+        if let Some(subfolder) = subfolder {
+            return Ok(presets
+                .iter()
+                .flat_map(|file| file.ancestors().skip(1))
+                .filter(|directory| {
+                    directory
+                        .file_name()
+                        .is_some_and(|name| name.eq_ignore_ascii_case(subfolder))
+                })
+                .min_by_key(|directory| directory.components().count())
+                .map(Path::to_path_buf));
+        }
+
+        let mut root: Option<PathBuf> = None;
+        for file in presets {
+            let parent = file.parent().unwrap_or(Path::new("")).to_path_buf();
+            root = Some(match root {
+                Some(root) => common_ancestor(&root, &parent),
+                None => parent,
+            });
+        }
+
+        Ok(root)
+    }
+
+    /// Returns the names of the folders inside the folder containing all presets that contain presets themselves
+    pub fn preset_subfolders(&self) -> ReShaderResult<Vec<String>> {
+        let Some(root) = self.presets_root(None)? else {
+            return Ok(Vec::new());
+        };
+        let mut subfolders = self
+            .preset_files()?
+            .iter()
+            .filter_map(|file| {
+                let mut components = file.strip_prefix(&root).ok()?.components();
+                let subfolder = components.next()?;
+                // presets directly inside the root aren't in a subfolder
+                components.next()?;
+                Some(subfolder.as_os_str().to_string_lossy().to_string())
+            })
+            .collect::<Vec<_>>();
+        subfolders.sort();
+        subfolders.dedup();
+        Ok(subfolders)
+    }
+
+    /// Returns the subfolder installed by default, see [`DEFAULT_PRESETS_SUBFOLDER`], if the presets contain it
+    pub fn default_presets_subfolder(&self) -> ReShaderResult<Option<String>> {
+        Ok(self
+            .preset_subfolders()?
+            .into_iter()
+            .find(|subfolder| subfolder.eq_ignore_ascii_case(DEFAULT_PRESETS_SUBFOLDER)))
+    }
+
+    fn preset_files(&self) -> ReShaderResult<Vec<&PathBuf>> {
+        let mut presets = Vec::new();
+        for file in &self.files {
+            let is_ini = file
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("ini"));
+            if is_ini && is_preset(&String::from_utf8_lossy(&self.read(file)?)) {
+                presets.push(file);
+            }
+        }
+        Ok(presets)
+    }
+
+    /// Finds the folder containing the `Shaders` folder with the effect files
+    pub fn shaders_root(&self) -> Option<PathBuf> {
+        self.files
+            .iter()
+            .filter(|file| {
+                file.extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("fx"))
+            })
+            .filter_map(|file| {
+                file.ancestors()
+                    .skip(1)
+                    .find(|ancestor| {
+                        ancestor
+                            .file_name()
+                            .is_some_and(|name| name.eq_ignore_ascii_case("Shaders"))
+                    })
+                    .map(|shaders| shaders.parent().unwrap_or(Path::new("")).to_path_buf())
+            })
+            .min_by_key(|root| root.components().count())
+    }
+
+    /// Copies all files inside a folder of the source to the target directory, replacing existing files
+    ///
+    /// Paths that would end up outside of the target directory are rejected.
+    pub fn extract(&self, root: &Path, target: &Path) -> ReShaderResult<()> {
+        for file in &self.files {
+            let Ok(relative) = file.strip_prefix(root) else {
+                continue;
+            };
+            let escapes = relative.components().any(|component| {
+                matches!(
+                    component,
+                    Component::ParentDir | Component::RootDir | Component::Prefix(_)
+                )
+            });
+            if escapes {
+                return Err(ReShaderError::UnsafePath(file.display().to_string()));
+            }
+
+            let target_file = target.join(relative);
+            std::fs::create_dir_all(target_file.parent().unwrap())?;
+            match &self.archive {
+                Some(_) => std::fs::write(&target_file, self.read(file)?)?,
+                None => {
+                    std::fs::copy(self.path.join(file), &target_file)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(&self, file: &Path) -> ReShaderResult<Vec<u8>> {
+        match &self.archive {
+            Some(archive) => {
+                let mut archive = archive.borrow_mut();
+                let mut entry = archive
+                    .by_name(&file.to_string_lossy())
+                    .map_err(|_| ReShaderError::ExtractZipFile)?;
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content)?;
+                Ok(content)
+            }
+            None => Ok(std::fs::read(self.path.join(file))?),
+        }
+    }
+}
+
+fn common_ancestor(a: &Path, b: &Path) -> PathBuf {
+    a.components()
+        .zip(b.components())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect()
+}
+
+fn is_git_path(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == ".git")
+}
+
+/// Lists the files of a folder, without following symlinks and skipping git metadata
+fn list_source_files(
+    root: &Path,
+    directory: &Path,
+    files: &mut Vec<PathBuf>,
+) -> ReShaderResult<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if entry.file_name() != ".git" {
+                list_source_files(root, &path, files)?;
+            }
+        } else if file_type.is_file() {
+            files.push(path.strip_prefix(root).unwrap().to_path_buf());
+        }
+    }
+
+    Ok(())
+}
+
+/// What a [`GShadeSource`] contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GShadeContent {
+    /// GShade presets
+    Presets,
+    /// GShade shaders and textures
+    Shaders,
+}
+
+impl GShadeSource {
+    /// Returns whether the source contains presets, shaders or both
+    pub fn contents(&self) -> ReShaderResult<Vec<GShadeContent>> {
+        let mut contents = Vec::new();
+        if self.presets_root(None)?.is_some() {
+            contents.push(GShadeContent::Presets);
+        }
+        if self.shaders_root().is_some() {
+            contents.push(GShadeContent::Shaders);
+        }
+        Ok(contents)
+    }
+}
+
+/// The GShade zip files found by [`find_gshade_downloads`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GShadeDownloads {
+    /// The newest zip file containing presets
+    pub presets: Option<PathBuf>,
+    /// The newest zip file containing shaders
+    pub shaders: Option<PathBuf>,
+}
+
+/// Looks for zip files with GShade presets and shaders in a directory, e.g. the downloads folder
+///
+/// The zip files are identified by their content, not by their name. The given recent files,
+/// e.g. from a [`DownloadWatcher`], are checked first, followed by the other zip files from newest to oldest.
+pub fn find_gshade_downloads(
+    directory: &Path,
+    recent: &[PathBuf],
+) -> ReShaderResult<GShadeDownloads> {
+    let mut zips = list_zip_files(directory)?;
+    zips.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+
+    let mut downloads = GShadeDownloads::default();
+    let candidates = recent.iter().chain(
+        zips.iter()
+            .map(|(path, _)| path)
+            .filter(|p| !recent.contains(p)),
+    );
+    for candidate in candidates {
+        if downloads.presets.is_some() && downloads.shaders.is_some() {
+            break;
+        }
+
+        // zip files that can't be read are most likely still being downloaded
+        let Ok(contents) = GShadeSource::open(candidate).and_then(|source| source.contents())
+        else {
+            continue;
+        };
+        if contents.contains(&GShadeContent::Presets) && downloads.presets.is_none() {
+            downloads.presets = Some(candidate.clone());
+        }
+        if contents.contains(&GShadeContent::Shaders) && downloads.shaders.is_none() {
+            downloads.shaders = Some(candidate.clone());
+        }
+    }
+
+    Ok(downloads)
+}
+
+/// Watches a directory for new zip files in the background, e.g. while the user downloads GShade
+///
+/// The directory is polled, so this works with any file system.
+#[derive(Debug)]
+pub struct DownloadWatcher {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Vec<PathBuf>>,
+}
+
+impl DownloadWatcher {
+    /// How often the directory is checked for new files
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Starts watching a directory
+    pub fn start(directory: &Path) -> Self {
+        let directory = directory.to_path_buf();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+
+        let handle = std::thread::spawn(move || {
+            let existing = list_zip_files(&directory).unwrap_or_default();
+            let mut new_files = Vec::new();
+            while !stopped.load(Ordering::Relaxed) {
+                std::thread::sleep(Self::POLL_INTERVAL);
+                // browsers download into a temporary file and rename it once it is complete
+                for (path, modified) in list_zip_files(&directory).unwrap_or_default() {
+                    let known = existing.contains(&(path.clone(), modified));
+                    if !known && !new_files.contains(&path) {
+                        new_files.push(path);
+                    }
+                }
+            }
+            new_files.reverse();
+            new_files
+        });
+
+        Self { stop, handle }
+    }
+
+    /// Stops watching and returns the zip files that appeared or changed, newest first
+    pub fn stop(self) -> Vec<PathBuf> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().unwrap_or_default()
+    }
+}
+
+fn list_zip_files(directory: &Path) -> ReShaderResult<Vec<(PathBuf, Option<SystemTime>)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_zip = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"));
+        if is_zip && path.is_file() {
+            let modified = entry.metadata()?.modified().ok();
+            files.push((path, modified));
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::CatalogEffect;
+    use crate::effect::parse_effect;
+
+    const PRESET: &str = "Techniques=MXAO@MXAO.fx
+TechniqueSorting=MXAO@MXAO.fx
+
+[MXAO.fx]
+MXAO_SSAO_AMOUNT=1.500000
+MXAO_GSHADE_ONLY=1
+fOldRadius=2.000000
+";
+
+    fn mapping() -> GShadeMapping {
+        GShadeMapping::parse(
+            r#"
+[effects]
+"MXAO.fx" = "qUINT_mxao.fx"
+
+[uniforms."MXAO.fx"]
+"fOldRadius" = "MXAO_SAMPLE_RADIUS"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn uniforms_are_renamed_and_unknown_ones_reported() {
+        let catalog = ShaderCatalog {
+            effects: vec![CatalogEffect {
+                path: PathBuf::from("Merged/Shaders/qUINT_mxao.fx"),
+                file: "qUINT_mxao.fx".to_string(),
+                collection: Some("qUINT".to_string()),
+                info: parse_effect(
+                    "uniform float MXAO_SSAO_AMOUNT = 1.0;
+                    uniform float MXAO_SAMPLE_RADIUS = 2.5;
+                    technique MXAO { }",
+                ),
+            }],
+        };
+        let mut preset = Preset::parse(PRESET);
+
+        let report = convert_preset(&mut preset, &mapping(), None, Some(&catalog));
+
+        assert_eq!(
+            preset.to_string(),
+            "Techniques=MXAO@qUINT_mxao.fx
+TechniqueSorting=MXAO@qUINT_mxao.fx
+
+[qUINT_mxao.fx]
+MXAO_SSAO_AMOUNT=1.500000
+MXAO_GSHADE_ONLY=1
+MXAO_SAMPLE_RADIUS=2.000000
+"
+        );
+        assert_eq!(
+            report.unmapped,
+            [("qUINT_mxao.fx".to_string(), "MXAO_GSHADE_ONLY".to_string())]
+        );
+    }
+
+    #[test]
+    fn unmapped_uniforms_are_reported_without_a_catalog() {
+        let mut preset = Preset::parse(PRESET);
+
+        let report = convert_preset(&mut preset, &mapping(), None, None);
+
+        assert_eq!(
+            report.unmapped,
+            [
+                ("qUINT_mxao.fx".to_string(), "MXAO_SSAO_AMOUNT".to_string()),
+                ("qUINT_mxao.fx".to_string(), "MXAO_GSHADE_ONLY".to_string())
+            ]
+        );
+    }
+}
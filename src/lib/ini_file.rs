@@ -0,0 +1,412 @@
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+};
+
+use crate::prelude::*;
+
+/// An INI file that keeps comments, blank lines and formatting of untouched entries
+///
+/// Unlike [`ini::Ini`], writing a parsed document back out yields the same content,
+/// which is required when editing files users might have modified by hand.
+/// Like ReShade, section names and keys are compared case-insensitively.
+#[derive(Debug, Clone)]
+pub struct IniDocument {
+    /// The first section is always the unnamed section containing the keys before any header
+    sections: Vec<IniSection>,
+    crlf: bool,
+    trailing_newline: bool,
+    /// Whether the file starts with a UTF-8 byte order mark, as written by some Windows editors
+    bom: bool,
+}
+
+/// A section of an [`IniDocument`]
+#[derive(Debug, Clone)]
+pub struct IniSection {
+    name: Option<String>,
+    header: Option<String>,
+    lines: Vec<IniLine>,
+}
+
+#[derive(Debug, Clone)]
+enum IniLine {
+    Entry {
+        key: String,
+        value: String,
+        /// The original line, `None` once the value has been changed
+        raw: Option<String>,
+    },
+    Other(String),
+}
+
+impl Default for IniDocument {
+    fn default() -> Self {
+        Self {
+            sections: vec![IniSection::new(None)],
+            crlf: false,
+            trailing_newline: true,
+            bom: false,
+        }
+    }
+}
+
+impl IniDocument {
+    /// Parses the contents of an INI file
+    pub fn parse(content: &str) -> Self {
+        let (content, bom) = match content.strip_prefix('\u{feff}') {
+            Some(content) => (content, true),
+            None => (content, false),
+        };
+        let mut document = IniDocument {
+            crlf: content.contains("\r\n"),
+            trailing_newline: content.is_empty() || content.ends_with('\n'),
+            bom,
+            ..Default::default()
+        };
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                let mut section = IniSection::new(Some(trimmed[1..trimmed.len() - 1].trim()));
+                section.header = Some(line.to_string());
+                document.sections.push(section);
+                continue;
+            }
+
+            let section = document.sections.last_mut().unwrap();
+            match trimmed.split_once('=') {
+                Some((key, value)) if !trimmed.starts_with(';') && !trimmed.starts_with('#') => {
+                    section.lines.push(IniLine::Entry {
+                        key: key.trim().to_string(),
+                        value: value.trim().to_string(),
+                        raw: Some(line.to_string()),
+                    })
+                }
+                _ => section.lines.push(IniLine::Other(line.to_string())),
+            }
+        }
+
+        document
+    }
+
+    /// Reads and parses an INI file
+    pub fn load(path: &Path) -> ReShaderResult<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&content))
+    }
+
+    /// Serializes and writes the INI file
+    pub fn save(&self, path: &Path) -> ReShaderResult<()> {
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Returns all sections, starting with the unnamed section
+    pub fn sections(&self) -> &[IniSection] {
+        &self.sections
+    }
+
+    /// Returns the section with the given name, `None` refers to the unnamed section
+    pub fn section(&self, name: Option<&str>) -> Option<&IniSection> {
+        self.sections.iter().find(|section| section.is_named(name))
+    }
+
+    /// Returns the value of a key in the given section
+    pub fn get(&self, section: Option<&str>, key: &str) -> Option<&str> {
+        self.section(section)?.get(key)
+    }
+
+    /// Sets the value of a key, creating the section and the key if necessary
+    ///
+    /// Returns whether the document changed.
+    pub fn set(&mut self, section: Option<&str>, key: &str, value: &str) -> bool {
+        self.section_or_insert(section).set(key, value)
+    }
+
+    /// Removes a key from the given section, returning whether it existed
+    pub fn remove(&mut self, section: Option<&str>, key: &str) -> bool {
+        match self.sections.iter_mut().find(|s| s.is_named(section)) {
+            Some(section) => section.remove(key),
+            None => false,
+        }
+    }
+
+    /// Renames a key in the given section, keeping its value and position
+    ///
+    /// Returns whether the key existed. If the new key exists already, the renamed key replaces it.
+    pub fn rename_key(&mut self, section: Option<&str>, key: &str, new_key: &str) -> bool {
+        let Some(section) = self.sections.iter_mut().find(|s| s.is_named(section)) else {
+            return false;
+        };
+        let Some(value) = section.get(key).map(str::to_string) else {
+            return false;
+        };
+        if !key.eq_ignore_ascii_case(new_key) {
+            section.remove(new_key);
+        }
+        for line in &mut section.lines {
+            if let IniLine::Entry {
+                key: entry_key,
+                value: entry_value,
+                raw,
+            } = line
+            {
+                if entry_key.eq_ignore_ascii_case(key) {
+                    *entry_key = new_key.to_string();
+                    *entry_value = value;
+                    *raw = None;
+                    break;
+                }
+            }
+        }
+        true
+    }
+
+    /// Removes a whole section, returning whether it existed
+    ///
+    /// The unnamed section can't be removed, only emptied.
+    pub fn remove_section(&mut self, name: &str) -> bool {
+        let before = self.sections.len();
+        self.sections
+            .retain(|section| !section.is_named(Some(name)));
+        self.sections.len() != before
+    }
+
+    /// Renames a section, returning whether it existed
+    ///
+    /// If a section with the new name exists already, the keys are moved into it
+    /// and keys that exist in both keep the value of the existing section.
+    pub fn rename_section(&mut self, name: &str, new_name: &str) -> bool {
+        let Some(index) = self
+            .sections
+            .iter()
+            .position(|section| section.is_named(Some(name)))
+        else {
+            return false;
+        };
+
+        if self
+            .sections
+            .iter()
+            .enumerate()
+            .any(|(other, section)| other != index && section.is_named(Some(new_name)))
+        {
+            let section = self.sections.remove(index);
+            let target = self.section_or_insert(Some(new_name));
+            for (key, value) in section.entries() {
+                if target.get(key).is_none() {
+                    target.set(key, value);
+                }
+            }
+        } else {
+            let section = &mut self.sections[index];
+            section.name = Some(new_name.to_string());
+            section.header = None;
+        }
+        true
+    }
+
+    fn section_or_insert(&mut self, name: Option<&str>) -> &mut IniSection {
+        if let Some(index) = self
+            .sections
+            .iter()
+            .position(|section| section.is_named(name))
+        {
+            return &mut self.sections[index];
+        }
+
+        // keep an empty line between the previous section and the new one
+        let previous = self.sections.last_mut().unwrap();
+        let previous_empty = previous.name.is_none() && previous.lines.is_empty();
+        let ends_blank =
+            matches!(previous.lines.last(), Some(IniLine::Other(line)) if line.trim().is_empty());
+        if !previous_empty && !ends_blank {
+            previous.lines.push(IniLine::Other(String::new()));
+        }
+
+        self.sections.push(IniSection::new(name));
+        self.sections.last_mut().unwrap()
+    }
+}
+
+impl Display for IniDocument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let line_ending = if self.crlf { "\r\n" } else { "\n" };
+        let lines = self
+            .sections
+            .iter()
+            .flat_map(|section| section.render_lines())
+            .collect::<Vec<_>>();
+
+        if self.bom {
+            write!(f, "\u{feff}")?;
+        }
+        write!(f, "{}", lines.join(line_ending))?;
+        if self.trailing_newline && !lines.is_empty() {
+            write!(f, "{line_ending}")?;
+        }
+        Ok(())
+    }
+}
+
+impl IniSection {
+    fn new(name: Option<&str>) -> Self {
+        Self {
+            name: name.map(str::to_string),
+            header: None,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the section, `None` for the unnamed section
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn is_named(&self, name: Option<&str>) -> bool {
+        match (&self.name, name) {
+            (Some(own), Some(name)) => own.eq_ignore_ascii_case(name),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value of a key in this section
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .find(|(entry_key, _)| entry_key.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns all keys and values of this section in order
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            IniLine::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+            IniLine::Other(_) => None,
+        })
+    }
+
+    /// Sets the value of a key, adding it after the last key of the section if necessary
+    ///
+    /// Returns whether the section changed.
+    pub fn set(&mut self, key: &str, value: &str) -> bool {
+        for line in &mut self.lines {
+            if let IniLine::Entry {
+                key: entry_key,
+                value: entry_value,
+                raw,
+            } = line
+            {
+                if entry_key.eq_ignore_ascii_case(key) {
+                    if entry_value == value {
+                        return false;
+                    }
+                    *entry_value = value.to_string();
+                    *raw = None;
+                    return true;
+                }
+            }
+        }
+
+        let index = self
+            .lines
+            .iter()
+            .rposition(|line| match line {
+                IniLine::Entry { .. } => true,
+                IniLine::Other(other) => !other.trim().is_empty(),
+            })
+            .map_or(0, |index| index + 1);
+        self.lines.insert(
+            index,
+            IniLine::Entry {
+                key: key.to_string(),
+                value: value.to_string(),
+                raw: None,
+            },
+        );
+        true
+    }
+
+    /// Removes a key, returning whether it existed
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.lines.len();
+        self.lines.retain(|line| match line {
+            IniLine::Entry { key: entry_key, .. } => !entry_key.eq_ignore_ascii_case(key),
+            IniLine::Other(_) => true,
+        });
+        self.lines.len() != before
+    }
+
+    fn render_lines(&self) -> Vec<String> {
+        let header = match (&self.header, &self.name) {
+            (Some(header), _) => Some(header.clone()),
+            (None, Some(name)) => Some(format!("[{name}]")),
+            (None, None) => None,
+        };
+
+        header
+            .into_iter()
+            .chain(self.lines.iter().map(|line| match line {
+                IniLine::Entry { raw: Some(raw), .. } => raw.clone(),
+                IniLine::Entry { key, value, .. } => format!("{key}={value}"),
+                IniLine::Other(other) => other.clone(),
+            }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESHADE_INI: &str = "; edited by hand
+[GENERAL]
+EffectSearchPaths=.\\reshade-shaders\\Shaders\\**
+PresetPath = .\\ReShadePreset.ini
+
+# the overlay settings
+[OVERLAY]
+TutorialProgress=4
+";
+
+    #[test]
+    fn round_trips_comments_and_blank_lines() {
+        assert_eq!(IniDocument::parse(RESHADE_INI).to_string(), RESHADE_INI);
+
+        let crlf = RESHADE_INI.replace('\n', "\r\n");
+        assert_eq!(IniDocument::parse(&crlf).to_string(), crlf);
+    }
+
+    #[test]
+    fn round_trips_files_with_a_bom() {
+        let content = format!("\u{feff}{RESHADE_INI}");
+        let mut document = IniDocument::parse(&content);
+
+        assert_eq!(
+            document.get(Some("GENERAL"), "PresetPath"),
+            Some(".\\ReShadePreset.ini")
+        );
+        assert_eq!(document.to_string(), content);
+
+        assert!(document.set(Some("OVERLAY"), "TutorialProgress", "0"));
+        assert_eq!(
+            document.to_string(),
+            content.replace("TutorialProgress=4", "TutorialProgress=0")
+        );
+    }
+
+    #[test]
+    fn sections_and_keys_are_case_insensitive() {
+        let mut document = IniDocument::parse("[general]\neffectsearchpaths=.\\Shaders\n");
+
+        assert_eq!(
+            document.get(Some("GENERAL"), "EffectSearchPaths"),
+            Some(".\\Shaders")
+        );
+        assert!(document.set(Some("GENERAL"), "EffectSearchPaths", ".\\Other"));
+        assert_eq!(
+            document.to_string(),
+            "[general]\neffectsearchpaths=.\\Other\n"
+        );
+    }
+}
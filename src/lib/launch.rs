@@ -0,0 +1,66 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::wine::merge_dll_overrides;
+
+/// Finds the game directory a launch command belongs to
+///
+/// Registered game paths that contain the working directory or one of the paths in `args`
+/// are preferred, the most specific one wins. If there is none, the directory of the first
+/// existing `.exe` in `args` is used.
+pub fn resolve_game_path(
+    game_paths: &[PathBuf],
+    working_dir: &Path,
+    args: &[String],
+) -> Option<PathBuf> {
+    let arg_paths = args
+        .iter()
+        .map(|arg| working_dir.join(arg))
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+
+    let candidates = std::iter::once(working_dir.to_path_buf())
+        .chain(arg_paths.iter().cloned())
+        .filter_map(|path| path.canonicalize().ok())
+        .collect::<Vec<_>>();
+
+    let registered = game_paths
+        .iter()
+        .filter_map(|game_path| {
+            let canonical = game_path.canonicalize().ok()?;
+            candidates
+                .iter()
+                .any(|candidate| candidate.starts_with(&canonical))
+                .then_some((canonical.components().count(), game_path))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, game_path)| game_path.clone());
+
+    registered.or_else(|| {
+        arg_paths
+            .iter()
+            .find(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("exe"))
+            })
+            .and_then(|path| path.parent())
+            .map(Path::to_path_buf)
+    })
+}
+
+/// Builds the command for the given arguments, with `WINEDLLOVERRIDES` set for the given DLLs
+///
+/// Any overrides already present in the environment are kept.
+pub fn prepare_command(command: &[String], dlls: &[&str]) -> Option<Command> {
+    let (program, args) = command.split_first()?;
+    let existing = std::env::var("WINEDLLOVERRIDES").ok();
+
+    let mut command = Command::new(program);
+    command.args(args).env(
+        "WINEDLLOVERRIDES",
+        merge_dll_overrides(existing.as_deref(), dlls),
+    );
+    Some(command)
+}
@@ -14,14 +14,60 @@ use dircpy::CopyBuilder;
 use lazy_static::lazy_static;
 use std::{
     fmt::{Display, Formatter},
-    io::{Read, Seek},
+    io::{Read, Seek, Write},
     path::{Path, PathBuf},
 };
 
 use crate::prelude::*;
 
+/// A searchable catalog of the downloaded effect files and their techniques
+pub mod catalog;
+/// Indexing the effect files of shader collections and resolving the collections a preset needs
+pub mod collection_index;
+/// The games ReShader manages and how they are stored in `config.toml`
+pub mod config;
+/// Finding and fixing the problems that keep ReShade from working in a game
+pub mod doctor;
+/// Parsing ReShade effect files
+pub mod effect;
+/// Finding the main executable of a game
+pub mod executable;
+/// Finding and removing what no game needs from the data directory
+pub mod gc;
+/// Finding and reading GShade presets and shaders and converting the presets for the ReShade shader collections
+pub mod gshade;
+/// Comment-preserving INI files
+pub mod ini_file;
+/// Helpers for launching games with ReShade enabled
+pub mod launch;
+/// Symlinking, hard linking, reflinking or copying files into game directories
+pub mod link;
+/// Advisory locks that keep ReShader processes from changing the same files at once
+pub mod lock;
+/// Owning the data directory, the config and the games ReShader manages
+pub mod manager;
+/// Planning the changes to a game directory before applying them
+pub mod plan;
 /// Common ReShader types and functions
 pub mod prelude;
+/// Reading, editing and creating ReShade presets
+pub mod preset;
+/// Importing, listing and applying presets kept in the data directory
+pub mod preset_store;
+/// Recording what ReShader created in a game directory
+pub mod receipt;
+/// A typed model of ReShade.ini
+pub mod reshade_ini;
+/// Reporting what ReShader installed for a game
+pub mod status;
+/// Rolling back changes to the filesystem when an operation fails or is interrupted
+pub mod transaction;
+/// Updating ReShade, the shaders and the presets of the games ReShader manages
+pub mod update;
+/// Checking presets against the shaders installed for a game
+pub mod validate;
+/// Reading and writing Wine registry files to set DLL overrides
+pub mod wine;
 
 static LIB_VERSION: &str = env!("CARGO_PKG_VERSION");
 static DEFAULT_INI: &str = include_str!("../../reshade.example.ini");
@@ -160,6 +206,19 @@ impl Display for ShaderCollection {
     }
 }
 
+/// Writes a file to a temporary file next to it first and renames it, so it is never left half-written
+pub fn write_atomically(path: &Path, content: &[u8]) -> ReShaderResult<()> {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".reshader-tmp");
+    let temporary = path.with_file_name(file_name);
+
+    let mut file = std::fs::File::create(&temporary)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
 /// Downloads a file from the given URL to the given path
 pub async fn download_file(client: &reqwest::Client, url: &str, path: &Path) -> ReShaderResult<()> {
     let resp = client
@@ -181,6 +240,9 @@ pub async fn download_file(client: &reqwest::Client, url: &str, path: &Path) ->
 }
 
 /// Downloads the specified shader collections to the given directory
+///
+/// All collections are downloaded before they are merged into a copy of the `Merged` directory,
+/// which then replaces it. If anything fails, the `Merged` directory is left as it was, see [`transaction::Transaction`].
 pub async fn download_shader_collections(
     collections: &[&ShaderCollection],
     directory: &Path,
@@ -195,34 +257,58 @@ pub async fn download_shader_collections(
         std::fs::create_dir(&zip_directory)?;
     }
 
+    let mut repo_directories = Vec::new();
     for collection in collections {
         collection.download(&zip_directory).await?;
         let root_dir = collection.unpack(&zip_directory)?;
+        repo_directories.push(zip_directory.join(root_dir));
+    }
 
-        let repo_directory = zip_directory.join(root_dir);
-        let repo_shader_directory = repo_directory.join("Shaders");
-        let repo_texture_directory = repo_directory.join("Textures");
-        let target_shader_directory = directory.join(&collection.install_path);
-        let target_texture_directory = directory.join(&collection.texture_install_path);
-        if !target_shader_directory.exists() {
-            std::fs::create_dir_all(&target_shader_directory)?;
+    let merged_directory = directory.join("Merged");
+    let staging_directory = directory.join("Merged.reshader-new");
+    transaction::Transaction::run(directory, |transaction| {
+        // left behind by an older version that didn't roll back
+        if staging_directory.exists() {
+            transaction.stage(&staging_directory)?;
         }
-        if !target_texture_directory.exists() {
-            std::fs::create_dir_all(&target_texture_directory)?;
+        transaction.create(&staging_directory)?;
+        if merged_directory.exists() {
+            CopyBuilder::new(&merged_directory, &staging_directory).run()?;
         }
 
-        if repo_shader_directory.exists() {
-            let builder = CopyBuilder::new(&repo_shader_directory, &target_shader_directory);
-            builder.overwrite(true).run()?;
-        }
+        for (collection, repo_directory) in collections.iter().zip(&repo_directories) {
+            // the install paths point into the Merged directory
+            let staged = |install_path: &str| {
+                let install_path = Path::new(install_path);
+                staging_directory.join(install_path.strip_prefix("Merged").unwrap_or(install_path))
+            };
+            let repo_shader_directory = repo_directory.join("Shaders");
+            let repo_texture_directory = repo_directory.join("Textures");
+            let target_shader_directory = staged(&collection.install_path);
+            let target_texture_directory = staged(&collection.texture_install_path);
+            if !target_shader_directory.exists() {
+                std::fs::create_dir_all(&target_shader_directory)?;
+            }
+            if !target_texture_directory.exists() {
+                std::fs::create_dir_all(&target_texture_directory)?;
+            }
+
+            if repo_shader_directory.exists() {
+                let builder = CopyBuilder::new(&repo_shader_directory, &target_shader_directory);
+                builder.overwrite(true).run()?;
+            }
 
-        if repo_texture_directory.exists() {
-            let builder = CopyBuilder::new(&repo_texture_directory, &target_texture_directory);
-            builder.overwrite(true).run()?;
+            if repo_texture_directory.exists() {
+                let builder = CopyBuilder::new(&repo_texture_directory, &target_texture_directory);
+                builder.overwrite(true).run()?;
+            }
         }
-    }
 
-    Ok(())
+        if merged_directory.exists() {
+            transaction.stage(&merged_directory)?;
+        }
+        transaction.rename(&staging_directory, &merged_directory)
+    })
 }
 
 /// Downloads the minimal ReShade shaders and textures to a directory
@@ -236,27 +322,119 @@ pub async fn download_minimal_reshade_shaders(directory: &Path) -> ReShaderResul
     Ok(())
 }
 
-/// Installs ReShade shaders and textures to a game directory by symlinking them
+/// Returns the names of the shader collections [`download_minimal_reshade_shaders`] downloads
+pub fn minimal_collection_names() -> Vec<String> {
+    SHADER_COLLECTIONS
+        .iter()
+        .filter(|collection| collection.enabled)
+        .map(|collection| collection.name.clone())
+        .collect()
+}
+
+/// Installs ReShade shaders and textures to a game directory by linking them
 ///
-/// This function will create a symlink called `reshade-shaders` in the game directory
-pub fn install_reshade_shaders(directory: &Path, game_path: &Path) -> ReShaderResult<()> {
-    let target_path = game_path.join("reshade-shaders");
-    // if target_path exists and is not a symlink, return an error
-    if target_path.exists() && std::fs::read_link(&target_path).is_err() {
-        return Err(ReShaderError::Symlink(
-            directory.to_str().unwrap().to_string(),
-            target_path.to_str().unwrap().to_string(),
-            "Directory already exists".to_string(),
-        ));
-    } else if target_path.exists() && std::fs::read_link(&target_path).is_ok() {
-        return Ok(());
-    }
+/// This function will create a link called `reshade-shaders` in the game directory, see [`link::LinkMode`],
+/// and record it in the receipt of the game, see [`receipt::Receipt`].
+pub fn install_reshade_shaders(
+    data_dir: &Path,
+    directory: &Path,
+    game_path: &Path,
+    link_mode: link::LinkMode,
+) -> ReShaderResult<()> {
+    plan_install_reshade_shaders(data_dir, directory, game_path, link_mode)?.execute(data_dir)
+}
 
-    std::os::unix::fs::symlink(directory, &target_path)?;
+/// Returns the changes [`install_reshade_shaders`] would make
+pub fn plan_install_reshade_shaders(
+    data_dir: &Path,
+    directory: &Path,
+    game_path: &Path,
+    link_mode: link::LinkMode,
+) -> ReShaderResult<plan::Plan> {
+    let receipt = receipt::Receipt::load(data_dir, game_path)?;
+    let mut plan = plan::Plan::new(game_path);
+    plan_link(
+        &mut plan,
+        data_dir,
+        &receipt,
+        link_mode,
+        directory,
+        &game_path.join("reshade-shaders"),
+    )?;
+    Ok(plan)
+}
 
+/// Plans replacing `target` in a game directory with a link to `source`
+///
+/// Only paths ReShader created are replaced, see [`is_ours`].
+fn plan_link(
+    plan: &mut plan::Plan,
+    data_dir: &Path,
+    receipt: &receipt::Receipt,
+    link_mode: link::LinkMode,
+    source: &Path,
+    target: &Path,
+) -> ReShaderResult<()> {
+    if std::fs::symlink_metadata(target).is_ok() {
+        if !is_ours(data_dir, receipt, target)? {
+            return Err(ReShaderError::Symlink(
+                source.to_str().unwrap().to_string(),
+                target.to_str().unwrap().to_string(),
+                "Path already exists and was not created by ReShader".to_string(),
+            ));
+        }
+
+        plan.push(plan::Action::Delete {
+            path: target.to_path_buf(),
+        });
+    }
+
+    plan.push(plan::Action::Link {
+        source: source.to_path_buf(),
+        path: target.to_path_buf(),
+        mode: link_mode,
+        replaced: None,
+    });
     Ok(())
 }
 
+/// Returns whether ReShader created a path: it is unchanged since it was recorded in the receipt of the game,
+/// or it is a symlink into the data directory, e.g. from before receipts existed
+fn is_ours(data_dir: &Path, receipt: &receipt::Receipt, path: &Path) -> ReShaderResult<bool> {
+    Ok(match receipt.entry(path) {
+        Some(entry) if entry.state()? == receipt::EntryState::Unchanged => true,
+        _ => links_into(data_dir, path),
+    })
+}
+
+/// Returns whether a plan may delete a path: ReShader created it, see [`is_ours`],
+/// or it is a copy of a downloaded ReShade DLL, see [`find_foreign_dlls`]
+fn is_deletable(
+    data_dir: &Path,
+    receipt: &receipt::Receipt,
+    game_path: &Path,
+    path: &Path,
+) -> ReShaderResult<bool> {
+    if is_ours(data_dir, receipt, path)? {
+        return Ok(true);
+    }
+    let dll = RESHADE_DLLS.iter().any(|name| game_path.join(name) == path);
+    Ok(dll && !find_foreign_dlls(data_dir, game_path)?.contains(&path.to_path_buf()))
+}
+
+/// Returns whether a path is a symlink to a file or directory inside the data directory
+fn links_into(data_dir: &Path, path: &Path) -> bool {
+    let Ok(target) = std::fs::read_link(path) else {
+        return false;
+    };
+    let target = path.parent().unwrap_or(path).join(target);
+    let target = target.canonicalize().unwrap_or(target);
+    let data_dir = data_dir
+        .canonicalize()
+        .unwrap_or_else(|_| data_dir.to_path_buf());
+    target.starts_with(data_dir)
+}
+
 /// Fetches the latest ReShade version from GitHub.
 ///
 /// Alternatively, if `version` is provided, it will return that version.
@@ -336,9 +514,7 @@ pub async fn download_reshade(
     let reshade_path = if let Some(specific_installer) = specific_installer {
         PathBuf::from(specific_installer)
     } else {
-        let reshade_url = get_latest_reshade_version(client, version, vanilla)
-            .await
-            .expect("Could not get latest ReShade version");
+        let reshade_url = get_latest_reshade_version(client, version, vanilla).await?;
         let reshade_path = tmp.path().join("reshade.exe");
 
         download_file(client, &reshade_url, &reshade_path).await?;
@@ -388,85 +564,285 @@ pub async fn download_reshade(
     Ok(())
 }
 
-/// Installs ReShade to the given game directory by symlinking the ReShade dll
-/// and d3dcompiler_47.dll to the game directory.
+/// The directory in the data directory that keeps the ReShade versions games are pinned to
+const VERSIONS_DIR: &str = "versions";
+
+/// Returns the directory a ReShade version games are pinned to is downloaded to, e.g. `versions/5.9.2`
+pub fn reshade_version_dir(data_dir: &Path, version: &str) -> PathBuf {
+    data_dir.join(VERSIONS_DIR).join(version)
+}
+
+/// The DLLs [`install_reshade`] puts into a game directory
+const RESHADE_DLLS: [&str; 2] = ["dxgi.dll", "d3dcompiler_47.dll"];
+
+/// The paths ReShader links into a game directory
+const LINKED_PATHS: [&str; 5] = [
+    "dxgi.dll",
+    "d3dcompiler_47.dll",
+    "reshade-shaders",
+    "gshade-presets",
+    "gshade-shaders",
+];
+
+/// Returns where a DLL that was in the way of ReShade is moved to, e.g. `dxgi.reshader-original.dll`
+pub fn original_dll_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.reshader-original.dll"))
+}
+
+/// Returns the DLLs in a game directory that [`install_reshade`] would replace but weren't created by ReShader,
+/// e.g. the `dxgi.dll` of DXVK.
+///
+/// Symlinks into the data directory, paths that are unchanged since they were recorded in the
+/// receipt of the game, and copies of the downloaded ReShade DLLs are not foreign.
+pub fn find_foreign_dlls(data_dir: &Path, game_path: &Path) -> ReShaderResult<Vec<PathBuf>> {
+    let receipt = receipt::Receipt::load(data_dir, game_path)?;
+    let known_hashes = [
+        "ReShade64.Addon.dll",
+        "ReShade64.Vanilla.dll",
+        "d3dcompiler_47.dll",
+    ]
+    .into_iter()
+    .map(|name| data_dir.join(name))
+    .filter(|path| path.is_file())
+    .map(|path| receipt::hash_file(&path))
+    .collect::<ReShaderResult<Vec<_>>>()?;
+
+    let mut foreign = Vec::new();
+    for name in RESHADE_DLLS {
+        let path = game_path.join(name);
+        let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+
+        let recorded = match receipt.entry(&path) {
+            Some(entry) => entry.state()? == receipt::EntryState::Unchanged,
+            None => false,
+        };
+        let ours = recorded
+            || if metadata.file_type().is_symlink() {
+                links_into(data_dir, &path)
+            } else {
+                metadata.is_file() && known_hashes.contains(&receipt::hash_file(&path)?)
+            };
+        if !ours {
+            foreign.push(path);
+        }
+    }
+
+    Ok(foreign)
+}
+
+/// Installs ReShade to the given game directory by linking the ReShade dll
+/// and d3dcompiler_47.dll to the game directory, see [`link::LinkMode`].
+///
+/// The symlinks are recorded in the receipt of the game, see [`receipt::Receipt`].
 ///
 /// Depending on the `vanilla` parameter, it will symlink the vanilla or addon version of ReShade.
+///
+/// DLLs that weren't created by ReShader (see [`find_foreign_dlls`]) are moved to [`original_dll_path`]
+/// and restored by [`uninstall`]. An older backup is replaced. If `chain_load` is set and the `dxgi.dll`
+/// was foreign, ReShade is configured to load it after itself.
+///
+/// The search paths ReShader needs are merged into an existing ReShade.ini, see [`reshade_ini::merge_reshade_ini`].
 pub async fn install_reshade(
     data_dir: &Path,
     game_path: &Path,
     vanilla: bool,
+    chain_load: bool,
+    link_mode: link::LinkMode,
 ) -> ReShaderResult<()> {
-    if game_path.join("dxgi.dll").exists() {
-        std::fs::remove_file(game_path.join("dxgi.dll"))?;
+    plan_install_reshade(data_dir, game_path, vanilla, chain_load, link_mode)?.execute(data_dir)
+}
+
+/// Returns the changes [`install_reshade`] would make
+pub fn plan_install_reshade(
+    data_dir: &Path,
+    game_path: &Path,
+    vanilla: bool,
+    chain_load: bool,
+    link_mode: link::LinkMode,
+) -> ReShaderResult<plan::Plan> {
+    plan_install_reshade_from(
+        data_dir, data_dir, game_path, vanilla, chain_load, link_mode,
+    )
+}
+
+/// Returns the changes [`install_reshade`] would make, taking the ReShade DLL from `reshade_dir`
+/// instead of the data directory, e.g. a [`reshade_version_dir`]
+pub fn plan_install_reshade_from(
+    data_dir: &Path,
+    reshade_dir: &Path,
+    game_path: &Path,
+    vanilla: bool,
+    chain_load: bool,
+    link_mode: link::LinkMode,
+) -> ReShaderResult<plan::Plan> {
+    let reshade_dll = if vanilla {
+        reshade_dir.join("ReShade64.Vanilla.dll")
+    } else {
+        reshade_dir.join("ReShade64.Addon.dll")
+    };
+
+    let foreign = find_foreign_dlls(data_dir, game_path)?;
+    let mut plan = plan::Plan::new(game_path);
+    for (name, source) in RESHADE_DLLS
+        .into_iter()
+        .zip([reshade_dll, data_dir.join("d3dcompiler_47.dll")])
+    {
+        let path = game_path.join(name);
+        let replaced = if foreign.contains(&path) {
+            let original = original_dll_path(&path);
+            plan.push(plan::Action::Move {
+                from: path.clone(),
+                to: original.clone(),
+            });
+            Some(original)
+        } else {
+            if std::fs::symlink_metadata(&path).is_ok() {
+                plan.push(plan::Action::Delete { path: path.clone() });
+            }
+            None
+        };
+
+        plan.push(plan::Action::Link {
+            source,
+            path,
+            mode: link_mode,
+            replaced,
+        });
     }
 
-    if game_path.join("d3dcompiler_47.dll").exists() {
-        std::fs::remove_file(game_path.join("d3dcompiler_47.dll"))?;
+    let ini_path = game_path.join("ReShade.ini");
+    let mut reshade_ini = reshade_ini::merged_reshade_ini(&ini_path)?;
+
+    let dxgi_path = game_path.join("dxgi.dll");
+    if chain_load && foreign.contains(&dxgi_path) {
+        let merged = reshade_ini.is_some();
+        let mut proxy_ini = match reshade_ini {
+            Some(reshade_ini) => reshade_ini,
+            None => reshade_ini::ReShadeIni::load(&ini_path)?,
+        };
+        let original = original_dll_path(&dxgi_path);
+        let changed = proxy_ini.set_proxy_library(&original.file_name().unwrap().to_string_lossy());
+        reshade_ini = (merged || changed).then_some(proxy_ini);
     }
 
-    if vanilla {
-        std::os::unix::fs::symlink(
-            data_dir.join("ReShade64.Vanilla.dll"),
-            game_path.join("dxgi.dll"),
-        )?;
+    if let Some(reshade_ini) = reshade_ini {
+        if ini_path.exists() {
+            plan.push(plan::Action::Backup {
+                path: ini_path.clone(),
+            });
+        }
+        plan.push(plan::Action::Write {
+            path: ini_path,
+            content: reshade_ini.to_string(),
+        });
+    }
+
+    Ok(plan)
+}
+
+/// Makes sure the ReShade files created by [`install_reshade`] are in place,
+/// recreating them if they are missing, dangling or copies of an older ReShade version.
+///
+/// The ReShade variant of an existing installation is kept. If there is none, the addon version
+/// is preferred if it has been downloaded.
+///
+/// Games linked to a [`reshade_version_dir`] keep that version.
+///
+/// Returns whether the files had to be repaired. Files ReShader didn't create are never replaced.
+pub async fn repair_reshade(
+    data_dir: &Path,
+    game_path: &Path,
+    link_mode: link::LinkMode,
+) -> ReShaderResult<bool> {
+    let dxgi_path = game_path.join("dxgi.dll");
+    let d3dcompiler_path = game_path.join("d3dcompiler_47.dll");
+    let receipt = receipt::Receipt::load(data_dir, game_path)?;
+
+    for path in [&dxgi_path, &d3dcompiler_path] {
+        if std::fs::symlink_metadata(path).is_ok() && !is_ours(data_dir, &receipt, path)? {
+            return Err(ReShaderError::Symlink(
+                data_dir.to_str().unwrap().to_string(),
+                path.to_str().unwrap().to_string(),
+                "File already exists and was not created by ReShader".to_string(),
+            ));
+        }
+    }
+
+    let dxgi_source = std::fs::read_link(&dxgi_path)
+        .ok()
+        .or_else(|| receipt.entry(&dxgi_path)?.source.clone());
+    let vanilla = match dxgi_source.as_ref().and_then(|source| source.file_name()) {
+        Some(name) => name == "ReShade64.Vanilla.dll",
+        None => {
+            !data_dir.join("ReShade64.Addon.dll").exists()
+                && data_dir.join("ReShade64.Vanilla.dll").exists()
+        }
+    };
+    let pinned_dir = receipt
+        .entry(&dxgi_path)
+        .and_then(|entry| entry.source.as_deref())
+        .or(dxgi_source.as_deref())
+        .and_then(Path::parent)
+        .filter(|dir| dir.starts_with(data_dir.join(VERSIONS_DIR)));
+    let reshade_dir = pinned_dir.unwrap_or(data_dir);
+    let reshade_dll = if vanilla {
+        reshade_dir.join("ReShade64.Vanilla.dll")
     } else {
-        std::os::unix::fs::symlink(
-            data_dir.join("ReShade64.Addon.dll"),
-            game_path.join("dxgi.dll"),
-        )?;
+        reshade_dir.join("ReShade64.Addon.dll")
+    };
+
+    let d3dcompiler_dll = data_dir.join("d3dcompiler_47.dll");
+    if is_up_to_date(&dxgi_path, &reshade_dll)?
+        && is_up_to_date(&d3dcompiler_path, &d3dcompiler_dll)?
+    {
+        return Ok(false);
     }
-    std::os::unix::fs::symlink(
-        data_dir.join("d3dcompiler_47.dll"),
-        game_path.join("d3dcompiler_47.dll"),
-    )?;
 
-    let ini_path = game_path.join("ReShade.ini");
-    if !ini_path.exists() {
-        std::fs::write(ini_path, DEFAULT_INI)?;
+    if !reshade_dll.exists() || !d3dcompiler_dll.exists() {
+        return Err(ReShaderError::ReShadeNotDownloaded);
     }
 
-    Ok(())
+    plan_install_reshade_from(data_dir, reshade_dir, game_path, vanilla, false, link_mode)?
+        .execute(data_dir)?;
+
+    Ok(true)
+}
+
+/// Returns whether a symlink isn't dangling, or a file has the same content as the file it was created from
+fn is_up_to_date(path: &Path, source: &Path) -> ReShaderResult<bool> {
+    Ok(match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => path.exists(),
+        Ok(_) => source.exists() && receipt::hash_file(path)? == receipt::hash_file(source)?,
+        Err(_) => false,
+    })
 }
 
 /// Installs GShade presets and shaders to the given directory.
 ///
-/// This does **not** download the presets and shaders, it just extracts them
-/// from the given zip files.
+/// This does **not** download the presets and shaders, it just copies them
+/// from the given zip files or folders, see [`gshade::GShadeSource`].
+/// Only the presets in the given subfolder are installed if there is one, see [`gshade::GShadeSource::presets_root`].
 pub async fn install_presets(
-    directory: &PathBuf,
-    presets_path: &PathBuf,
-    shaders_path: &PathBuf,
+    directory: &Path,
+    presets_path: &Path,
+    presets_subfolder: Option<&str>,
+    shaders_path: &Path,
 ) -> ReShaderResult<()> {
-    let file = std::fs::File::open(presets_path)?;
-    let mut presets_zip =
-        zip::read::ZipArchive::new(file).map_err(|_| ReShaderError::ReadZipFile)?;
-    presets_zip
-        .extract(directory)
-        .map_err(|_| ReShaderError::ExtractZipFile)?;
-
-    CopyBuilder::new(
-        directory.join("./GShade-Presets-master/FFXIV"),
-        directory.join("reshade-presets"),
-    )
-    .overwrite(true)
-    .run()?;
-    std::fs::remove_dir_all(directory.join("GShade-Presets-master"))?;
-
-    let file = std::fs::File::open(shaders_path).expect("unable to open shaders file");
-    let mut shaders_zip =
-        zip::read::ZipArchive::new(file).map_err(|_| ReShaderError::ReadZipFile)?;
-    shaders_zip
-        .extract(directory)
-        .map_err(|_| ReShaderError::ExtractZipFile)?;
-
-    CopyBuilder::new(
-        directory.join("./GShade-C-Shaders-main/gshade-shaders"),
-        directory.join("reshade-shaders"),
-    )
-    .overwrite(true)
-    .run()?;
-    std::fs::remove_dir_all(directory.join("GShade-C-Shaders-main"))?;
+    let presets = gshade::GShadeSource::open(presets_path)?;
+    let presets_root = presets.presets_root(presets_subfolder)?.ok_or_else(|| {
+        let location = presets_path.join(presets_subfolder.unwrap_or_default());
+        ReShaderError::NoGShadePresets(location.display().to_string())
+    })?;
+    copy_gshade_source(&presets, &presets_root, &directory.join("reshade-presets"))?;
+
+    let shaders = gshade::GShadeSource::open(shaders_path)?;
+    let shaders_root = shaders
+        .shaders_root()
+        .ok_or_else(|| ReShaderError::NoGShadeShaders(shaders_path.display().to_string()))?;
+    copy_gshade_source(&shaders, &shaders_root, &directory.join("reshade-shaders"))?;
 
     let intermediate_path = directory.join("reshade-shaders").join("Intermediate");
     if !intermediate_path.exists() {
@@ -476,44 +852,137 @@ pub async fn install_presets(
     Ok(())
 }
 
-/// Uninstalls ReShade from the given game directory by removing the ReShade dll
-/// (dxgi.dll) and d3dcompiler_47.dll.
-///
-/// INI files are not removed.
-pub fn uninstall(game_path: &Path) -> ReShaderResult<()> {
-    let dxgi_path = PathBuf::from(&game_path).join("dxgi.dll");
-    let d3dcompiler_path = PathBuf::from(&game_path).join("d3dcompiler_47.dll");
-    let presets_path = PathBuf::from(&game_path).join("reshade-presets");
-    let shaders_path = PathBuf::from(&game_path).join("reshade-shaders");
-
-    if dxgi_path.exists() {
-        std::fs::remove_file(dxgi_path)?;
-    }
-    if d3dcompiler_path.exists() {
-        std::fs::remove_file(d3dcompiler_path)?;
+fn copy_gshade_source(
+    source: &gshade::GShadeSource,
+    root: &Path,
+    target: &Path,
+) -> ReShaderResult<()> {
+    // the gshade folders of a game installed by ReShader link back to the target
+    let source_root = source.path().join(root).canonicalize().ok();
+    if source_root.is_some() && source_root == target.canonicalize().ok() {
+        return Ok(());
     }
-    if presets_path.exists() {
-        std::fs::remove_dir_all(presets_path)?;
+
+    source.extract(root, target)
+}
+
+/// Uninstalls ReShade from the given game directory by removing the paths listed in its receipt
+///
+/// Paths that have been changed since ReShader created them are left alone and returned.
+/// Files that were replaced by ReShader are moved back.
+/// Games installed before receipts existed only have the known ReShader symlinks into the data directory removed.
+///
+/// The `ReShade.ini` ReShader created and its backup are not removed, they are returned as well.
+pub fn uninstall(data_dir: &Path, game_path: &Path) -> ReShaderResult<Vec<PathBuf>> {
+    let plan = plan_uninstall(data_dir, game_path)?;
+    plan.execute(data_dir)?;
+    Ok(plan.kept)
+}
+
+/// Returns the changes [`uninstall`] would make, the paths it would leave alone are in [`plan::Plan::kept`]
+pub fn plan_uninstall(data_dir: &Path, game_path: &Path) -> ReShaderResult<plan::Plan> {
+    let mut plan = plan::Plan::new(game_path);
+
+    if !receipt::Receipt::exists(data_dir, game_path) {
+        for name in LINKED_PATHS {
+            let path = game_path.join(name);
+            if links_into(data_dir, &path) {
+                plan.push(plan::Action::Delete { path });
+            } else if std::fs::symlink_metadata(&path).is_ok() {
+                plan.kept.push(path);
+            }
+        }
+        return Ok(plan);
     }
-    if shaders_path.exists() {
-        std::fs::remove_dir_all(shaders_path)?;
+
+    let receipt = receipt::Receipt::load(data_dir, game_path)?;
+    for entry in receipt.entries.iter().rev() {
+        let path = entry.path.clone();
+        if entry.keep {
+            plan.kept.push(path.clone());
+            plan.push(plan::Action::Forget { path });
+            continue;
+        }
+        match entry.state()? {
+            receipt::EntryState::Unchanged => plan.push(plan::Action::Delete { path }),
+            receipt::EntryState::Changed => {
+                plan.kept.push(path.clone());
+                plan.push(plan::Action::Forget { path });
+                continue;
+            }
+            receipt::EntryState::Missing => plan.push(plan::Action::Forget { path }),
+        }
+
+        if let Some(original) = &entry.replaced {
+            if std::fs::symlink_metadata(original).is_ok() {
+                plan.push(plan::Action::Move {
+                    from: original.clone(),
+                    to: entry.path.clone(),
+                });
+            }
+        }
     }
 
-    Ok(())
+    Ok(plan)
 }
 
-/// Installs the GShade presets and shaders to the given game directory by symlinking
-pub fn install_preset_for_game(data_dir: &Path, game_path: &Path) -> ReShaderResult<()> {
-    let target_preset_path = PathBuf::from(game_path).join("gshade-presets");
-    let target_shaders_path = PathBuf::from(game_path).join("gshade-shaders");
+/// Returns the changes removing a path ReShader linked into a game directory would make,
+/// e.g. `gshade-shaders` when the game switches to the ReShade shaders
+///
+/// Only paths ReShader created are removed, see [`is_ours`]. Other paths are left alone and listed in
+/// [`plan::Plan::kept`].
+pub fn plan_remove_link(
+    data_dir: &Path,
+    game_path: &Path,
+    name: &str,
+) -> ReShaderResult<plan::Plan> {
+    let receipt = receipt::Receipt::load(data_dir, game_path)?;
+    let mut plan = plan::Plan::new(game_path);
+    let path = game_path.join(name);
+    if std::fs::symlink_metadata(&path).is_err() {
+        return Ok(plan);
+    }
 
-    if std::fs::read_link(&target_preset_path).is_ok()
-        || std::fs::read_link(&target_shaders_path).is_ok()
-    {
-        return Ok(());
+    if is_ours(data_dir, &receipt, &path)? {
+        plan.push(plan::Action::Delete { path });
+    } else {
+        plan.kept.push(path);
     }
+    Ok(plan)
+}
 
-    std::os::unix::fs::symlink(data_dir.join("reshade-presets"), target_preset_path)?;
-    std::os::unix::fs::symlink(data_dir.join("reshade-shaders"), target_shaders_path)?;
-    Ok(())
+/// Installs the GShade presets and shaders to the given game directory by linking, see [`link::LinkMode`]
+pub fn install_preset_for_game(
+    data_dir: &Path,
+    game_path: &Path,
+    link_mode: link::LinkMode,
+) -> ReShaderResult<()> {
+    plan_install_preset_for_game(data_dir, game_path, link_mode)?.execute(data_dir)
+}
+
+/// Returns the changes [`install_preset_for_game`] would make
+pub fn plan_install_preset_for_game(
+    data_dir: &Path,
+    game_path: &Path,
+    link_mode: link::LinkMode,
+) -> ReShaderResult<plan::Plan> {
+    let receipt = receipt::Receipt::load(data_dir, game_path)?;
+    let mut plan = plan::Plan::new(game_path);
+    plan_link(
+        &mut plan,
+        data_dir,
+        &receipt,
+        link_mode,
+        &data_dir.join("reshade-presets"),
+        &game_path.join("gshade-presets"),
+    )?;
+    plan_link(
+        &mut plan,
+        data_dir,
+        &receipt,
+        link_mode,
+        &data_dir.join("reshade-shaders"),
+        &game_path.join("gshade-shaders"),
+    )?;
+    Ok(plan)
 }
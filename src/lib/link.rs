@@ -0,0 +1,220 @@
+use std::{
+    fs::File,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use crate::prelude::*;
+
+/// The `FICLONE` ioctl, which makes a file share the data of another file until either is changed
+const FICLONE: u32 = 0x4004_9409;
+
+/// How ReShader puts files from the data directory into a game directory
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Display,
+    EnumString,
+    EnumIter,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum LinkMode {
+    /// An absolute symbolic link, the game has to be able to see the data directory
+    #[default]
+    Symlink,
+    /// A symbolic link relative to the game directory
+    RelativeSymlink,
+    /// A hard link for every file, the game has to be on the same filesystem as the data directory
+    Hardlink,
+    /// A copy-on-write clone of every file, which needs a filesystem like Btrfs or XFS
+    Reflink,
+    /// A full copy of every file
+    Copy,
+}
+
+impl LinkMode {
+    /// Returns whether this mode creates symbolic links
+    pub fn is_symlink(self) -> bool {
+        matches!(self, LinkMode::Symlink | LinkMode::RelativeSymlink)
+    }
+
+    /// Creates `target` from `source`
+    ///
+    /// Modes that work on files recreate directories and link every file inside them.
+    pub fn link(self, source: &Path, target: &Path) -> ReShaderResult<()> {
+        if !self.is_symlink() && source.is_dir() {
+            std::fs::create_dir_all(target)?;
+            for entry in std::fs::read_dir(source)? {
+                let entry = entry?;
+                self.link(&entry.path(), &target.join(entry.file_name()))?;
+            }
+            return Ok(());
+        }
+
+        let result = match self {
+            LinkMode::Symlink => std::os::unix::fs::symlink(source, target),
+            LinkMode::RelativeSymlink => relative_path(source, target.parent().unwrap())
+                .and_then(|relative| std::os::unix::fs::symlink(relative, target)),
+            // hard links to symlinks would point to wherever the symlink is resolved from
+            LinkMode::Hardlink => source
+                .canonicalize()
+                .and_then(|source| std::fs::hard_link(source, target)),
+            LinkMode::Reflink => reflink(source, target),
+            LinkMode::Copy => std::fs::copy(source, target).map(|_| ()),
+        };
+        result.map_err(|e| {
+            ReShaderError::Link(
+                self.to_string(),
+                target.to_str().unwrap().to_string(),
+                e.to_string(),
+            )
+        })
+    }
+}
+
+/// Returns `path` relative to the directory `base`, after resolving symlinks in both
+pub fn relative_path(path: &Path, base: &Path) -> std::io::Result<PathBuf> {
+    let path = path.canonicalize()?;
+    let base = base.canonicalize()?;
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in base.components().skip(common) {
+        relative.push("..");
+    }
+    for component in path.components().skip(common) {
+        relative.push(component);
+    }
+    Ok(relative)
+}
+
+fn reflink(source: &Path, target: &Path) -> std::io::Result<()> {
+    let source_file = File::open(source)?;
+    let target_file = File::create(target)?;
+
+    // SAFETY: both file descriptors stay open until the call returns
+    let result = unsafe {
+        libc::ioctl(
+            target_file.as_raw_fd(),
+            FICLONE as _,
+            source_file.as_raw_fd(),
+        )
+    };
+    if result != 0 {
+        let error = std::io::Error::last_os_error();
+        drop(target_file);
+        let _ = std::fs::remove_file(target);
+        return Err(match error.raw_os_error() {
+            // the filesystem can't clone files, or the files are on different filesystems
+            Some(libc::EOPNOTSUPP | libc::EXDEV | libc::EINVAL) => std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "the filesystem can't clone the file into the game directory, use another link mode",
+            ),
+            _ => error,
+        });
+    }
+
+    target_file.set_permissions(source_file.metadata()?.permissions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+    use tempdir::TempDir;
+
+    /// Creates a source directory with a file and a nested file
+    fn setup(tmp: &TempDir) -> PathBuf {
+        let source = tmp.path().join("source");
+        std::fs::create_dir_all(source.join("Shaders")).unwrap();
+        std::fs::write(source.join("ReShade.fxh"), "header").unwrap();
+        std::fs::write(source.join("Shaders").join("Bloom.fx"), "bloom").unwrap();
+        source
+    }
+
+    fn inode(path: &Path) -> u64 {
+        std::fs::metadata(path).unwrap().ino()
+    }
+
+    #[test]
+    fn symlinks_point_to_the_source() {
+        let tmp = TempDir::new("reshader-link-symlink").unwrap();
+        let source = setup(&tmp);
+        let game = tmp.path().join("game");
+        std::fs::create_dir_all(&game).unwrap();
+
+        LinkMode::Symlink
+            .link(&source, &game.join("absolute"))
+            .unwrap();
+        LinkMode::RelativeSymlink
+            .link(&source, &game.join("relative"))
+            .unwrap();
+
+        assert_eq!(std::fs::read_link(game.join("absolute")).unwrap(), source);
+        assert_eq!(
+            std::fs::read_link(game.join("relative")).unwrap(),
+            Path::new("../source")
+        );
+        for name in ["absolute", "relative"] {
+            let path = game.join(name).join("Shaders").join("Bloom.fx");
+            assert_eq!(std::fs::read_to_string(path).unwrap(), "bloom");
+        }
+    }
+
+    #[test]
+    fn hardlinks_and_copies_recreate_directories() {
+        let tmp = TempDir::new("reshader-link-files").unwrap();
+        let source = setup(&tmp);
+        let hardlinked = tmp.path().join("hardlinked");
+        let copied = tmp.path().join("copied");
+
+        LinkMode::Hardlink.link(&source, &hardlinked).unwrap();
+        LinkMode::Copy.link(&source, &copied).unwrap();
+
+        let file = Path::new("Shaders").join("Bloom.fx");
+        for target in [&hardlinked, &copied] {
+            assert!(!std::fs::symlink_metadata(target)
+                .unwrap()
+                .file_type()
+                .is_symlink());
+            assert_eq!(
+                std::fs::read_to_string(target.join(&file)).unwrap(),
+                "bloom"
+            );
+        }
+        assert_eq!(inode(&hardlinked.join(&file)), inode(&source.join(&file)));
+        assert_ne!(inode(&copied.join(&file)), inode(&source.join(&file)));
+    }
+
+    #[test]
+    fn reflinks_clone_or_fail_without_leaving_files() {
+        let tmp = TempDir::new("reshader-link-reflink").unwrap();
+        let source = setup(&tmp).join("ReShade.fxh");
+        let target = tmp.path().join("ReShade.fxh");
+
+        // whether the file can be cloned depends on the filesystem of the temporary directory
+        match LinkMode::Reflink.link(&source, &target) {
+            Ok(()) => assert_eq!(std::fs::read_to_string(&target).unwrap(), "header"),
+            Err(ReShaderError::Link(mode, path, _)) => {
+                assert_eq!(mode, "reflink");
+                assert_eq!(path, target.to_str().unwrap());
+                assert!(std::fs::symlink_metadata(&target).is_err());
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::ErrorKind,
+    os::fd::AsRawFd,
+    path::Path,
+};
+
+use crate::prelude::*;
+
+/// The name of the file in the data directory that is locked while ReShader changes it
+pub const LOCK_FILE: &str = ".lock";
+
+/// An advisory lock on a file, which is released when it is dropped
+///
+/// Only other ReShader processes respect the lock, other programs can still change the files it protects.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Locks a file exclusively, waiting until other processes have released it
+    ///
+    /// The file is created if it doesn't exist.
+    pub fn exclusive(path: &Path) -> ReShaderResult<Self> {
+        Ok(Self::lock(path, libc::LOCK_EX)?.expect("a blocking lock is always acquired"))
+    }
+
+    /// Locks a file exclusively, or returns `None` if another process holds the lock
+    pub fn try_exclusive(path: &Path) -> ReShaderResult<Option<Self>> {
+        Self::lock(path, libc::LOCK_EX | libc::LOCK_NB)
+    }
+
+    fn lock(path: &Path, operation: libc::c_int) -> ReShaderResult<Option<Self>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+
+        loop {
+            // SAFETY: the file descriptor stays open until the lock is dropped
+            if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+                return Ok(Some(Self { file }));
+            }
+            let error = std::io::Error::last_os_error();
+            match error.kind() {
+                ErrorKind::Interrupted => continue,
+                ErrorKind::WouldBlock => return Ok(None),
+                _ => return Err(error.into()),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // SAFETY: the file descriptor is still open, closing it would release the lock as well
+        unsafe {
+            libc::flock(self.file.as_raw_fd(), libc::LOCK_UN);
+        }
+    }
+}
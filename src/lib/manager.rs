@@ -0,0 +1,463 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError, Weak},
+};
+
+use crate::{
+    config::{Config, GameEntry},
+    gc::{find_artifacts, remove_artifacts, Artifact},
+    link::LinkMode,
+    lock::{FileLock, LOCK_FILE},
+    plan::Plan,
+    prelude::*,
+    reshade_version_dir,
+    status::{game_status, GameStatus},
+    transaction::recover,
+    update::{update_games, GameUpdate},
+    ShaderCollection,
+};
+
+static QUALIFIER: &str = "eu";
+static ORGANIZATION: &str = "cozysoft";
+static APPLICATION: &str = "reshader";
+
+/// The name of the config file inside the config directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Owns the data directory and the config, and installs ReShade for the games in it
+///
+/// Opening a manager rolls back operations that were interrupted and migrates old configs.
+/// Changes to the config are only written by [`ReShaderManager::save`].
+///
+/// Several managers, e.g. in different ReShader processes, can use the same directories at once:
+/// the data directory is locked while it is changed, and saving the config keeps the changes the others saved.
+#[derive(Debug)]
+pub struct ReShaderManager {
+    data_dir: PathBuf,
+    config_path: PathBuf,
+    config: Config,
+    /// The config as it was last loaded or saved, to find out what changed since then
+    base: Config,
+    migrated_from: Option<PathBuf>,
+    recovered: usize,
+    lock: Mutex<Weak<FileLock>>,
+    on_lock_wait: Option<fn()>,
+}
+
+impl ReShaderManager {
+    /// Opens the config and data directories of the current user, e.g. `~/.config/reshader` and `~/.local/share/reshader`
+    pub fn open() -> ReShaderResult<Self> {
+        let dirs = directories::ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION)
+            .ok_or(ReShaderError::NoHomeDirectory)?;
+        Self::with_directories(dirs.config_dir(), dirs.data_dir())
+    }
+
+    /// Opens the given config and data directories, creating them if needed
+    pub fn with_directories(config_dir: &Path, data_dir: &Path) -> ReShaderResult<Self> {
+        std::fs::create_dir_all(config_dir)?;
+        std::fs::create_dir_all(data_dir)?;
+
+        let recovered = match FileLock::try_exclusive(&data_dir.join(LOCK_FILE))? {
+            Some(_lock) => recover(data_dir)?,
+            // the operations of the process holding the lock may still be running
+            None => 0,
+        };
+
+        let config_path = config_dir.join(CONFIG_FILE);
+        let (config, migrated_from) = {
+            let _lock = FileLock::exclusive(&config_lock_path(&config_path))?;
+            if config_path.exists() {
+                Config::load(&config_path)?
+            } else {
+                let config = Config::default();
+                config.save(&config_path)?;
+                (config, None)
+            }
+        };
+
+        Ok(Self {
+            data_dir: data_dir.to_path_buf(),
+            config_path,
+            base: config.clone(),
+            config,
+            migrated_from,
+            recovered,
+            lock: Mutex::new(Weak::new()),
+            on_lock_wait: None,
+        })
+    }
+
+    /// Returns the directory ReShade, the shaders and the presets are downloaded to
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Returns the location of the config file
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+
+    /// Returns the config
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns the config for changing it, see [`ReShaderManager::save`]
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    /// Returns where the config was backed up if it was migrated from an older format when opening
+    pub fn migrated_from(&self) -> Option<&Path> {
+        self.migrated_from.as_deref()
+    }
+
+    /// Returns how many interrupted operations were rolled back when opening
+    pub fn recovered(&self) -> usize {
+        self.recovered
+    }
+
+    /// Returns the games ReShade was installed for
+    pub fn games(&self) -> &[GameEntry] {
+        &self.config.games
+    }
+
+    /// Returns the directories of the games ReShade was installed for
+    pub fn game_paths(&self) -> Vec<PathBuf> {
+        self.config.game_paths()
+    }
+
+    /// Writes the config, keeping the changes other processes saved since it was loaded, see [`Config::merge`]
+    pub fn save(&mut self) -> ReShaderResult<()> {
+        let _lock = FileLock::exclusive(&config_lock_path(&self.config_path))?;
+        if self.config_path.exists() {
+            let (theirs, _) = Config::load(&self.config_path)?;
+            self.config.merge(&self.base, theirs);
+        }
+        self.config.save(&self.config_path)?;
+        self.base = self.config.clone();
+        Ok(())
+    }
+
+    /// Calls `callback` before waiting for another process to release the data directory
+    pub fn on_lock_wait(&mut self, callback: fn()) {
+        self.on_lock_wait = Some(callback);
+    }
+
+    /// Locks the data directory against other ReShader processes until the returned lock is dropped
+    ///
+    /// The methods of the manager that change files lock it themselves.
+    /// Locking it again while the manager holds the lock doesn't wait.
+    pub fn lock(&self) -> ReShaderResult<Arc<FileLock>> {
+        let mut held = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(lock) = held.upgrade() {
+            return Ok(lock);
+        }
+
+        let path = self.data_dir.join(LOCK_FILE);
+        let lock = match FileLock::try_exclusive(&path)? {
+            Some(lock) => lock,
+            None => {
+                if let Some(callback) = self.on_lock_wait {
+                    callback();
+                }
+                FileLock::exclusive(&path)?
+            }
+        };
+        let lock = Arc::new(lock);
+        *held = Arc::downgrade(&lock);
+        Ok(lock)
+    }
+
+    /// Downloads ReShade to the data directory, see [`crate::download_reshade`]
+    pub async fn download_reshade(
+        &self,
+        client: &reqwest::Client,
+        vanilla: bool,
+        version: Option<String>,
+        specific_installer: &Option<String>,
+    ) -> ReShaderResult<()> {
+        let _lock = self.lock()?;
+        crate::download_reshade(client, &self.data_dir, vanilla, version, specific_installer).await
+    }
+
+    /// Downloads a ReShade version games can be pinned to, returning its [`crate::reshade_version_dir`]
+    pub async fn download_pinned_reshade(
+        &self,
+        client: &reqwest::Client,
+        vanilla: bool,
+        version: &str,
+        specific_installer: &Option<String>,
+    ) -> ReShaderResult<PathBuf> {
+        let _lock = self.lock()?;
+        let reshade_dir = reshade_version_dir(&self.data_dir, version);
+        std::fs::create_dir_all(&reshade_dir)?;
+        crate::download_reshade(
+            client,
+            &reshade_dir,
+            vanilla,
+            Some(version.to_string()),
+            specific_installer,
+        )
+        .await?;
+        Ok(reshade_dir)
+    }
+
+    /// Downloads shader collections and merges them into the data directory, see [`crate::download_shader_collections`]
+    pub async fn download_shaders(&self, collections: &[&ShaderCollection]) -> ReShaderResult<()> {
+        let _lock = self.lock()?;
+        crate::download_shader_collections(collections, &self.data_dir).await
+    }
+
+    /// Downloads the shader collections ReShade installs by default, see [`crate::download_minimal_reshade_shaders`]
+    pub async fn download_minimal_shaders(&self) -> ReShaderResult<()> {
+        let _lock = self.lock()?;
+        crate::download_minimal_reshade_shaders(&self.data_dir).await
+    }
+
+    /// Extracts GShade presets and shaders into the data directory, see [`crate::install_presets`]
+    pub async fn download_presets(
+        &self,
+        presets_path: &Path,
+        presets_subfolder: Option<&str>,
+        shaders_path: &Path,
+    ) -> ReShaderResult<()> {
+        let _lock = self.lock()?;
+        crate::install_presets(
+            &self.data_dir,
+            presets_path,
+            presets_subfolder,
+            shaders_path,
+        )
+        .await
+    }
+
+    /// Returns the changes [`ReShaderManager::install_reshade`] would make to a game directory
+    pub fn plan_install_reshade(
+        &self,
+        game_path: &Path,
+        vanilla: bool,
+        chain_load: bool,
+        link_mode: Option<LinkMode>,
+        pinned_version: Option<&str>,
+    ) -> ReShaderResult<Plan> {
+        let reshade_dir = match pinned_version {
+            Some(version) => reshade_version_dir(&self.data_dir, version),
+            None => self.data_dir.clone(),
+        };
+        crate::plan_install_reshade_from(
+            &self.data_dir,
+            &reshade_dir,
+            game_path,
+            vanilla,
+            chain_load,
+            self.link_mode(game_path, link_mode),
+        )
+    }
+
+    /// Installs ReShade for a game and registers it
+    ///
+    /// With a pinned version, the one downloaded by [`ReShaderManager::download_pinned_reshade`] is installed
+    /// and the game keeps it when updating, otherwise the game is unpinned.
+    /// Without a link mode, the one used for the game before is used.
+    pub fn install_reshade(
+        &mut self,
+        game_path: &Path,
+        vanilla: bool,
+        chain_load: bool,
+        link_mode: Option<LinkMode>,
+        pinned_version: Option<&str>,
+    ) -> ReShaderResult<&mut GameEntry> {
+        let _lock = self.lock()?;
+        self.plan_install_reshade(game_path, vanilla, chain_load, link_mode, pinned_version)?
+            .execute(&self.data_dir)?;
+        let link_mode = self.config.use_link_mode(game_path, link_mode);
+        let game = self.config.register_reshade(game_path, vanilla, link_mode);
+        game.pinned_version = pinned_version.map(String::from);
+        Ok(game)
+    }
+
+    /// Returns the changes [`ReShaderManager::install_reshade_shaders`] would make to a game directory
+    pub fn plan_install_reshade_shaders(
+        &self,
+        game_path: &Path,
+        link_mode: Option<LinkMode>,
+    ) -> ReShaderResult<Plan> {
+        crate::plan_install_reshade_shaders(
+            &self.data_dir,
+            &self.data_dir.join("Merged"),
+            game_path,
+            self.link_mode(game_path, link_mode),
+        )
+    }
+
+    /// Links the downloaded shaders into a game directory and remembers the collections for the game
+    pub fn install_reshade_shaders<S: AsRef<str>>(
+        &mut self,
+        game_path: &Path,
+        link_mode: Option<LinkMode>,
+        collections: &[S],
+    ) -> ReShaderResult<()> {
+        let _lock = self.lock()?;
+        self.plan_install_reshade_shaders(game_path, link_mode)?
+            .execute(&self.data_dir)?;
+        self.config.use_link_mode(game_path, link_mode);
+        self.config.add_collections(game_path, collections);
+        Ok(())
+    }
+
+    /// Returns the changes [`ReShaderManager::install_presets_for_game`] would make to a game directory
+    pub fn plan_install_presets_for_game(
+        &self,
+        game_path: &Path,
+        link_mode: Option<LinkMode>,
+    ) -> ReShaderResult<Plan> {
+        crate::plan_install_preset_for_game(
+            &self.data_dir,
+            game_path,
+            self.link_mode(game_path, link_mode),
+        )
+    }
+
+    /// Links the downloaded GShade presets and shaders into a game directory
+    pub fn install_presets_for_game(
+        &mut self,
+        game_path: &Path,
+        link_mode: Option<LinkMode>,
+    ) -> ReShaderResult<()> {
+        let _lock = self.lock()?;
+        self.plan_install_presets_for_game(game_path, link_mode)?
+            .execute(&self.data_dir)?;
+        self.config.use_link_mode(game_path, link_mode);
+        Ok(())
+    }
+
+    /// Removes a path ReShader linked into a game directory, returning it if it was left alone,
+    /// see [`crate::plan_remove_link`]
+    pub fn remove_link(&self, game_path: &Path, name: &str) -> ReShaderResult<Vec<PathBuf>> {
+        let _lock = self.lock()?;
+        let plan = crate::plan_remove_link(&self.data_dir, game_path, name)?;
+        plan.execute(&self.data_dir)?;
+        Ok(plan.kept)
+    }
+
+    /// Returns the changes uninstalling ReShade would make to a game directory, see [`crate::plan_uninstall`]
+    pub fn plan_uninstall(&self, game_path: &Path) -> ReShaderResult<Plan> {
+        crate::plan_uninstall(&self.data_dir, game_path)
+    }
+
+    /// Applies a plan made by [`ReShaderManager::plan_uninstall`] and forgets the game
+    pub fn apply_uninstall(&mut self, plan: &Plan) -> ReShaderResult<()> {
+        {
+            let _lock = self.lock()?;
+            plan.execute(&self.data_dir)?;
+        }
+        self.config.remove_game(&plan.game_path);
+        Ok(())
+    }
+
+    /// Uninstalls ReShade from a game and forgets it, returning the paths that were left alone
+    pub fn uninstall(&mut self, game_path: &Path) -> ReShaderResult<Vec<PathBuf>> {
+        let plan = self.plan_uninstall(game_path)?;
+        self.apply_uninstall(&plan)?;
+        Ok(plan.kept)
+    }
+
+    /// Applies a plan, e.g. one that was saved as JSON, see [`Plan::execute`]
+    ///
+    /// Only plans for registered games are applied.
+    pub fn apply_plan(&self, plan: &Plan) -> ReShaderResult<()> {
+        if self.config.game(&plan.game_path).is_none() {
+            return Err(ReShaderError::Plan(format!(
+                "{} is not a registered game",
+                plan.game_path.display()
+            )));
+        }
+        let _lock = self.lock()?;
+        plan.execute(&self.data_dir)
+    }
+
+    /// Recreates the ReShade files of a game if needed, see [`crate::repair_reshade`]
+    pub async fn repair(&self, game_path: &Path) -> ReShaderResult<bool> {
+        let _lock = self.lock()?;
+        crate::repair_reshade(&self.data_dir, game_path, self.config.link_mode(game_path)).await
+    }
+
+    /// Updates ReShade, the shaders and the presets of registered games, see [`update_games`]
+    ///
+    /// The paths are resolved like `--game`, see [`Config::resolve_game`]. Returns [`ReShaderError::UnknownGame`]
+    /// before anything is updated if one of them isn't a registered game.
+    pub async fn update(
+        &self,
+        client: &reqwest::Client,
+        game_paths: &[PathBuf],
+        specific_installer: &Option<String>,
+    ) -> ReShaderResult<Vec<GameUpdate>> {
+        let _lock = self.lock()?;
+        let games = game_paths
+            .iter()
+            .map(|game_path| {
+                let (resolved, _) = self.config.resolve_game(game_path)?;
+                self.config
+                    .game(&resolved)
+                    .cloned()
+                    .ok_or_else(|| ReShaderError::UnknownGame(game_path.display().to_string()))
+            })
+            .collect::<ReShaderResult<Vec<_>>>()?;
+        Ok(update_games(client, &self.data_dir, &games, specific_installer).await)
+    }
+
+    /// Finds what no game needs in the data directory, see [`find_artifacts`]
+    ///
+    /// The games other processes saved to the config since it was loaded are taken into account.
+    pub fn find_garbage(&self) -> ReShaderResult<Vec<Artifact>> {
+        let _lock = self.lock()?;
+        find_artifacts(&self.data_dir, &self.latest_config()?.games)
+    }
+
+    /// Removes what [`ReShaderManager::find_garbage`] found, returning the space that was freed in bytes
+    ///
+    /// Another process may have installed a game using some of the artifacts since they were found,
+    /// so they are looked for again and only the ones that are still unused are removed.
+    pub fn remove_garbage(&self, artifacts: &[Artifact]) -> ReShaderResult<u64> {
+        let _lock = self.lock()?;
+        let unused = find_artifacts(&self.data_dir, &self.latest_config()?.games)?
+            .into_iter()
+            .filter(|unused| {
+                artifacts
+                    .iter()
+                    .any(|artifact| artifact.path == unused.path)
+            })
+            .collect::<Vec<_>>();
+        remove_artifacts(&self.data_dir, &unused)
+    }
+
+    /// Returns the config with the changes other processes saved since it was loaded, without saving it
+    fn latest_config(&self) -> ReShaderResult<Config> {
+        let mut config = self.config.clone();
+        let _lock = FileLock::exclusive(&config_lock_path(&self.config_path))?;
+        if self.config_path.exists() {
+            let (theirs, _) = Config::load(&self.config_path)?;
+            config.merge(&self.base, theirs);
+        }
+        Ok(config)
+    }
+
+    /// Returns the given link mode, or the one used for the game before
+    fn link_mode(&self, game_path: &Path, link_mode: Option<LinkMode>) -> LinkMode {
+        link_mode.unwrap_or_else(|| self.config.link_mode(game_path))
+    }
+
+    /// Returns what is installed for every game, with the error for the games that couldn't be checked
+    pub fn statuses(&self) -> Vec<(PathBuf, ReShaderResult<GameStatus>)> {
+        self.config
+            .games
+            .iter()
+            .map(|game| (game.path.clone(), game_status(&self.data_dir, &game.path)))
+            .collect()
+    }
+}
+
+fn config_lock_path(config_path: &Path) -> PathBuf {
+    config_path.with_extension("toml.lock")
+}
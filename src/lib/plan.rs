@@ -0,0 +1,487 @@
+use std::{
+    fmt::{Display, Formatter},
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    link::LinkMode,
+    prelude::*,
+    receipt::Receipt,
+    reshade_ini::{backup, backup_path},
+    transaction::Transaction,
+};
+
+/// The changes an operation makes to a game directory
+///
+/// Plans are created without touching the game directory, so they can be shown to the user
+/// or saved as JSON before [`Plan::execute`] applies them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    /// The game directory
+    pub game_path: PathBuf,
+    /// The changes, in the order they are applied
+    pub actions: Vec<Action>,
+    /// Paths that are left alone because they changed since ReShader created them, or hold settings like `ReShade.ini`
+    #[serde(default)]
+    pub kept: Vec<PathBuf>,
+}
+
+/// A single change in a [`Plan`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum Action {
+    /// Removes a file, a symlink or a directory with everything inside it
+    Delete {
+        /// The path to remove
+        path: PathBuf,
+    },
+    /// Renames a file, e.g. to keep a DLL ReShade replaces
+    Move {
+        /// The current location
+        from: PathBuf,
+        /// The new location
+        to: PathBuf,
+    },
+    /// Creates a path from a file or directory in the data directory, see [`LinkMode`]
+    Link {
+        /// The file or directory in the data directory
+        source: PathBuf,
+        /// The path to create
+        path: PathBuf,
+        /// How the path is created
+        mode: LinkMode,
+        /// Where the file previously at `path` has been moved to
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        replaced: Option<PathBuf>,
+    },
+    /// Copies a file to its backup location unless it has been backed up before, see [`backup`]
+    Backup {
+        /// The file to back up
+        path: PathBuf,
+    },
+    /// Writes text to a file, replacing its content
+    Write {
+        /// The file to write
+        path: PathBuf,
+        /// The new content
+        content: String,
+    },
+    /// Removes a path from the receipt of the game without touching it
+    Forget {
+        /// The path to forget
+        path: PathBuf,
+    },
+}
+
+impl Plan {
+    /// Creates an empty plan for a game directory
+    pub fn new(game_path: &Path) -> Self {
+        Self {
+            game_path: game_path.to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns whether the plan doesn't change anything
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Adds a change to the end of the plan
+    pub fn push(&mut self, action: Action) {
+        self.actions.push(action);
+    }
+
+    /// Applies the changes in order and records them in the receipt of the game
+    ///
+    /// As the game directory may have changed since the plan was made, or the plan was edited, every change
+    /// is checked again before it is applied: paths must be inside the game directory without going through
+    /// a symlink, links must come from the data directory, and only paths ReShader created may be deleted or
+    /// replaced by a move. The only files that may be moved are a DLL ReShade replaces, to its
+    /// [`crate::original_dll_path`] by the plan that links ReShade over it, and the file the receipt records
+    /// as replaced, back to where it was. Files may only be written if they don't exist, are recorded in
+    /// the receipt, or have been backed up by the plan before.
+    /// If a change fails, the changes applied until then are rolled back, see [`Transaction`].
+    pub fn execute(&self, data_dir: &Path) -> ReShaderResult<()> {
+        Transaction::run(data_dir, |transaction| {
+            transaction.save(&Receipt::path(data_dir, &self.game_path))?;
+            let mut receipt = Receipt::load(data_dir, &self.game_path)?;
+            // the entries removed while applying still tell where replaced files belong
+            let recorded = receipt.clone();
+            for index in 0..self.actions.len() {
+                self.check(index, data_dir, &recorded, &receipt)?;
+                self.actions[index].apply(&mut receipt, transaction)?;
+            }
+            receipt.save(data_dir)
+        })
+    }
+
+    fn check(
+        &self,
+        index: usize,
+        data_dir: &Path,
+        recorded: &Receipt,
+        receipt: &Receipt,
+    ) -> ReShaderResult<()> {
+        let (applied, rest) = self.actions.split_at(index);
+        let action = &rest[0];
+        let paths = match action {
+            Action::Move { from, to } => vec![from, to],
+            Action::Link { source, path, .. } => {
+                if !is_inside(source, data_dir) {
+                    return Err(ReShaderError::Plan(format!(
+                        "{} is not inside the data directory",
+                        source.display()
+                    )));
+                }
+                vec![path]
+            }
+            Action::Delete { path }
+            | Action::Backup { path }
+            | Action::Write { path, .. }
+            | Action::Forget { path } => vec![path],
+        };
+        if let Some(path) = paths
+            .iter()
+            .find(|path| !is_inside_game(path, &self.game_path))
+        {
+            return Err(ReShaderError::Plan(format!(
+                "{} is not inside {}",
+                path.display(),
+                self.game_path.display()
+            )));
+        }
+
+        let deletable = |path: &Path| -> ReShaderResult<bool> {
+            Ok(std::fs::symlink_metadata(path).is_err()
+                || crate::is_deletable(data_dir, receipt, &self.game_path, path)?)
+        };
+        let changed = |path: &Path| {
+            Err(ReShaderError::Plan(format!(
+                "{} has changed since the plan was made",
+                path.display()
+            )))
+        };
+        match action {
+            Action::Delete { path } if !deletable(path)? => changed(path),
+            Action::Move { to, .. } if !deletable(to)? => changed(to),
+            Action::Move { from, to }
+                if !self.is_replacement_move(from, to, &rest[1..], recorded) =>
+            {
+                Err(ReShaderError::Plan(format!(
+                    "{} is not a DLL replaced by ReShade and may not be moved",
+                    from.display()
+                )))
+            }
+            Action::Write { path, .. }
+                if std::fs::symlink_metadata(path).is_ok()
+                    && receipt.entry(path).is_none()
+                    && !applied.iter().any(
+                        |action| matches!(action, Action::Backup { path: backup } if backup == path),
+                    ) =>
+            {
+                Err(ReShaderError::Plan(format!(
+                    "{} wasn't created by ReShader and has to be backed up before it is written",
+                    path.display()
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns whether a move puts a DLL ReShade replaces out of the way, or puts it back on uninstall
+    fn is_replacement_move(
+        &self,
+        from: &Path,
+        to: &Path,
+        following: &[Action],
+        recorded: &Receipt,
+    ) -> bool {
+        let is_reshade_dll = |path: &Path| {
+            crate::RESHADE_DLLS
+                .iter()
+                .any(|name| self.game_path.join(name) == path)
+        };
+
+        let moved_away = is_reshade_dll(from)
+            && crate::original_dll_path(from) == to
+            && following.iter().any(|action| {
+                matches!(action, Action::Link { path, replaced: Some(replaced), .. }
+                    if path == from && replaced == to)
+            });
+        let moved_back = is_reshade_dll(to)
+            && recorded
+                .entry(to)
+                .and_then(|entry| entry.replaced.as_deref())
+                == Some(from);
+        moved_away || moved_back
+    }
+}
+
+/// Returns whether a path is inside the game directory without leaving it through `..` or a symlinked directory
+fn is_inside_game(path: &Path, game_path: &Path) -> bool {
+    is_inside(path, game_path)
+        && path
+            .ancestors()
+            .skip(1)
+            .take_while(|ancestor| *ancestor != game_path)
+            .all(|ancestor| {
+                !std::fs::symlink_metadata(ancestor)
+                    .is_ok_and(|metadata| metadata.file_type().is_symlink())
+            })
+}
+
+/// Returns whether a path is inside a directory without leaving it through `..`
+fn is_inside(path: &Path, directory: &Path) -> bool {
+    path != directory
+        && path.starts_with(directory)
+        && !path
+            .components()
+            .any(|component| component == Component::ParentDir)
+}
+
+impl Action {
+    fn apply(&self, receipt: &mut Receipt, transaction: &mut Transaction) -> ReShaderResult<()> {
+        match self {
+            Action::Delete { path } => {
+                // the path is only removed once all changes have been made
+                transaction.stage(path)?;
+                receipt.forget(path);
+            }
+            Action::Move { from, to } => {
+                if std::fs::symlink_metadata(to).is_ok() {
+                    transaction.stage(to)?;
+                }
+                transaction.rename(from, to)?;
+            }
+            Action::Link {
+                source,
+                path,
+                mode,
+                replaced,
+            } => {
+                transaction.create(path)?;
+                mode.link(source, path)?;
+                receipt.record_link(path, source)?;
+                if let Some(replaced) = replaced {
+                    receipt.record_replaced(path, replaced);
+                }
+            }
+            Action::Backup { path } => {
+                let backup_path = backup_path(path);
+                let created = path.exists() && !backup_path.exists();
+                if created {
+                    transaction.create(&backup_path)?;
+                }
+                backup(path)?;
+                if created {
+                    receipt.record_kept(&backup_path)?;
+                }
+            }
+            Action::Write { path, content } => {
+                let created = std::fs::symlink_metadata(path).is_err();
+                transaction.save(path)?;
+                std::fs::write(path, content)?;
+                if created {
+                    receipt.record_kept(path)?;
+                }
+            }
+            Action::Forget { path } => receipt.forget(path),
+        }
+        Ok(())
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Delete { path } => write!(f, "delete {}", path.display()),
+            Action::Move { from, to } => write!(f, "move {} to {}", from.display(), to.display()),
+            Action::Link {
+                source, path, mode, ..
+            } => write!(f, "{mode} {} to {}", source.display(), path.display()),
+            Action::Backup { path } => write!(f, "back up {}", path.display()),
+            Action::Write { path, .. } => write!(f, "write {}", path.display()),
+            Action::Forget { path } => write!(f, "stop tracking {}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    /// Creates a data directory with a ReShade DLL and a game directory with the DLL it replaces
+    fn setup(tmp: &TempDir) -> (PathBuf, PathBuf) {
+        let data_dir = tmp.path().join("data");
+        let game_path = tmp.path().join("game");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::create_dir_all(&game_path).unwrap();
+        std::fs::write(data_dir.join("ReShade64.dll"), "reshade").unwrap();
+        std::fs::write(game_path.join("dxgi.dll"), "original").unwrap();
+        (data_dir, game_path)
+    }
+
+    fn plan(game_path: &Path, actions: Vec<Action>) -> Plan {
+        Plan {
+            game_path: game_path.to_path_buf(),
+            actions,
+            kept: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn replacing_a_dll_moves_it_and_moves_it_back() {
+        let tmp = TempDir::new("reshader-plan-replace").unwrap();
+        let (data_dir, game_path) = setup(&tmp);
+        let dll = game_path.join("dxgi.dll");
+        let original = crate::original_dll_path(&dll);
+
+        plan(
+            &game_path,
+            vec![
+                Action::Move {
+                    from: dll.clone(),
+                    to: original.clone(),
+                },
+                Action::Link {
+                    source: data_dir.join("ReShade64.dll"),
+                    path: dll.clone(),
+                    mode: LinkMode::Symlink,
+                    replaced: Some(original.clone()),
+                },
+            ],
+        )
+        .execute(&data_dir)
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&dll).unwrap(), "reshade");
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "original");
+
+        plan(
+            &game_path,
+            vec![
+                Action::Move {
+                    from: original.clone(),
+                    to: dll.clone(),
+                },
+                Action::Forget { path: dll.clone() },
+            ],
+        )
+        .execute(&data_dir)
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&dll).unwrap(), "original");
+        assert!(!original.exists());
+    }
+
+    #[test]
+    fn moving_a_dll_without_replacing_it_is_rejected() {
+        let tmp = TempDir::new("reshader-plan-move-alone").unwrap();
+        let (data_dir, game_path) = setup(&tmp);
+        let dll = game_path.join("dxgi.dll");
+
+        let result = plan(
+            &game_path,
+            vec![Action::Move {
+                from: dll.clone(),
+                to: crate::original_dll_path(&dll),
+            }],
+        )
+        .execute(&data_dir);
+
+        assert!(matches!(result, Err(ReShaderError::Plan(_))));
+        assert_eq!(std::fs::read_to_string(&dll).unwrap(), "original");
+    }
+
+    #[test]
+    fn moving_other_files_is_rejected() {
+        let tmp = TempDir::new("reshader-plan-move-foreign").unwrap();
+        let (data_dir, game_path) = setup(&tmp);
+        let save = game_path.join("save.dat");
+        std::fs::write(&save, "save").unwrap();
+        // an original DLL the receipt doesn't know about belongs to the user
+        let original = crate::original_dll_path(&game_path.join("d3dcompiler_47.dll"));
+        std::fs::write(&original, "user").unwrap();
+
+        for (from, to) in [
+            (save.clone(), game_path.join("save.dat.old")),
+            (original.clone(), game_path.join("d3dcompiler_47.dll")),
+        ] {
+            let result = plan(&game_path, vec![Action::Move { from, to }]).execute(&data_dir);
+            assert!(matches!(result, Err(ReShaderError::Plan(_))));
+        }
+        assert_eq!(std::fs::read_to_string(&save).unwrap(), "save");
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "user");
+        assert!(!game_path.join("d3dcompiler_47.dll").exists());
+    }
+
+    #[test]
+    fn deleting_files_reshader_did_not_create_is_rejected() {
+        let tmp = TempDir::new("reshader-plan-delete").unwrap();
+        let (data_dir, game_path) = setup(&tmp);
+        let save = game_path.join("save.dat");
+        std::fs::write(&save, "save").unwrap();
+
+        let result =
+            plan(&game_path, vec![Action::Delete { path: save.clone() }]).execute(&data_dir);
+
+        assert!(matches!(result, Err(ReShaderError::Plan(_))));
+        assert_eq!(std::fs::read_to_string(&save).unwrap(), "save");
+    }
+
+    #[test]
+    fn paths_through_symlinks_are_rejected() {
+        let tmp = TempDir::new("reshader-plan-symlink").unwrap();
+        let (data_dir, game_path) = setup(&tmp);
+        let outside = tmp.path().join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, game_path.join("escape")).unwrap();
+        let path = game_path.join("escape").join("ReShade.ini");
+
+        for action in [
+            Action::Write {
+                path: path.clone(),
+                content: "written".to_string(),
+            },
+            Action::Link {
+                source: data_dir.join("ReShade64.dll"),
+                path: path.clone(),
+                mode: LinkMode::Copy,
+                replaced: None,
+            },
+            Action::Delete { path: path.clone() },
+        ] {
+            let result = plan(&game_path, vec![action]).execute(&data_dir);
+            assert!(matches!(result, Err(ReShaderError::Plan(_))));
+        }
+        assert!(!outside.join("ReShade.ini").exists());
+    }
+
+    #[test]
+    fn paths_outside_the_game_are_rejected() {
+        let tmp = TempDir::new("reshader-plan-outside").unwrap();
+        let (data_dir, game_path) = setup(&tmp);
+
+        let result = plan(
+            &game_path,
+            vec![
+                Action::Write {
+                    path: game_path.join("..").join("written"),
+                    content: "written".to_string(),
+                },
+                Action::Link {
+                    source: tmp.path().join("elsewhere"),
+                    path: game_path.join("linked"),
+                    mode: LinkMode::Symlink,
+                    replaced: None,
+                },
+            ],
+        )
+        .execute(&data_dir);
+
+        assert!(matches!(result, Err(ReShaderError::Plan(_))));
+        assert!(!tmp.path().join("written").exists());
+    }
+}
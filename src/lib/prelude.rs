@@ -17,6 +17,10 @@ pub enum ReShaderError {
     /// Occurs when there is a problem symlinking a file or a directory
     Symlink(String, String, String),
 
+    #[error("Could not create {1} as {0}: {2}")]
+    /// Occurs when a file or directory can't be put into a game directory with the selected [`crate::link::LinkMode`]
+    Link(String, String, String),
+
     #[error("ReShade installer had no zip file")]
     /// Occurs when the ReShade installer doesn't have a zip file (it's missing its byte sequence for the zip file)
     NoZipFile,
@@ -25,6 +29,10 @@ pub enum ReShaderError {
     /// Occurs when the ReShade installer doesn't have a ReShade64.dll file contained in it
     NoReShade64Dll,
 
+    #[error("ReShade has not been downloaded yet")]
+    /// Occurs when ReShade should be linked into a game, but the ReShade DLLs are missing from the data directory
+    ReShadeNotDownloaded,
+
     #[error("Unable to read zip file")]
     /// Occurs when the ReShade installer's zip file cannot be read
     ReadZipFile,
@@ -42,6 +50,73 @@ pub enum ReShaderError {
     /// Occurs when the branch for shaders or presets cannot be merged
     MergeConflict(String, String),
 
+    #[error("Invalid Wine registry file at line {0}: {1}")]
+    /// Occurs when a Wine registry file (e.g. `user.reg`) cannot be parsed
+    RegistryParse(usize, String),
+
+    #[error("Unknown ReShade.ini key {1} in section {0}")]
+    /// Occurs when a key that ReShade doesn't know about should be changed in ReShade.ini
+    UnknownIniKey(String, String),
+    #[error("Invalid value for ReShade.ini key {1} in section {0}: {2}")]
+    /// Occurs when a value doesn't have the type ReShade expects for a key in ReShade.ini
+    InvalidIniValue(String, String, String),
+
+    #[error("Invalid collection index: {0}")]
+    /// Occurs when the index of shader collections cannot be read or written
+    Index(String),
+
+    #[error("Invalid install receipt: {0}")]
+    /// Occurs when the receipt of the paths ReShader created in a game cannot be read or written
+    Receipt(String),
+
+    #[error("Invalid plan: {0}")]
+    /// Occurs when a plan saved as JSON can't be read
+    Plan(String),
+
+    #[error("Invalid transaction journal: {0}")]
+    /// Occurs when the journal of an unfinished transaction cannot be read or written
+    Journal(String),
+    #[error("{0} (rolling back the changes failed as well: {1})")]
+    /// Occurs when rolling back a transaction fails after one of its changes failed
+    ///
+    /// The first argument is the error of the change, the second the error of the rollback.
+    Rollback(Box<ReShaderError>, Box<ReShaderError>),
+
+    #[error("Could not find the home directory of the current user")]
+    /// Occurs when the config and data directories can't be determined
+    NoHomeDirectory,
+
+    #[error("More than one registered game is inside {0}, use the directory of the game")]
+    /// Occurs when a directory given as a game contains several registered games
+    AmbiguousGame(String),
+
+    #[error("{0} is not a registered game, install ReShade for it first")]
+    /// Occurs when a game should be changed that ReShade hasn't been installed for
+    UnknownGame(String),
+
+    #[error("Invalid config: {0}")]
+    /// Occurs when the config file cannot be read or migrated
+    Config(String),
+
+    #[error("No preset called {0} has been imported")]
+    /// Occurs when a preset cannot be found in the preset store
+    PresetNotFound(String),
+    #[error("More than one preset is called {0}, use its full name")]
+    /// Occurs when a preset name matches several presets in the preset store
+    AmbiguousPreset(String),
+    #[error("Invalid GShade mapping table: {0}")]
+    /// Occurs when the table mapping GShade effects to ReShade effects cannot be read
+    GShadeMapping(String),
+    #[error("Could not find GShade presets in {0}")]
+    /// Occurs when a zip file or folder doesn't contain any presets
+    NoGShadePresets(String),
+    #[error("Could not find GShade shaders in {0}")]
+    /// Occurs when a zip file or folder doesn't contain a Shaders folder with effect files
+    NoGShadeShaders(String),
+    #[error("Refusing to extract {0} outside of the target directory")]
+    /// Occurs when a path in a zip file or folder would be extracted outside of the directory it is extracted to
+    UnsafePath(String),
+
     #[error(transparent)]
     /// Forwards the errors from `std::io::Error`
     Io(#[from] std::io::Error),
@@ -0,0 +1,365 @@
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{
+    ini_file::IniDocument,
+    prelude::*,
+    reshade_ini::{split_list, KeyBinding},
+};
+
+/// A technique enabled in a preset, e.g. `SMAA@SMAA.fx`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Technique {
+    /// The name of the technique
+    pub name: String,
+    /// The effect file declaring the technique, missing in presets made by old ReShade versions
+    pub file: Option<String>,
+}
+
+impl Technique {
+    /// Creates a technique reference
+    pub fn new(name: &str, file: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            file: file.map(str::to_string),
+        }
+    }
+}
+
+impl FromStr for Technique {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.split_once('@') {
+            Some((name, file)) => Self::new(name.trim(), Some(file.trim())),
+            None => Self::new(s.trim(), None),
+        })
+    }
+}
+
+impl Display for Technique {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}@{}", self.name, file),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// The value of a uniform variable in a preset
+///
+/// ReShade stores all uniforms as comma separated numbers, e.g. `0.5,0.5,1.0` for a `float3`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformValue(pub Vec<f64>);
+
+impl FromStr for UniformValue {
+    type Err = std::num::ParseFloatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|component| component.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map(UniformValue)
+    }
+}
+
+impl Display for UniformValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let components = self
+            .0
+            .iter()
+            .map(|component| format_component(*component, None))
+            .collect::<Vec<_>>();
+        write!(f, "{}", components.join(","))
+    }
+}
+
+/// Formats a component of a [`UniformValue`], with the given number of decimals if there is one
+fn format_component(component: f64, decimals: Option<usize>) -> String {
+    match decimals {
+        Some(decimals) => format!("{component:.decimals$}"),
+        None if component.fract() == 0.0 && component.abs() < 1e15 => {
+            format!("{}", component as i64)
+        }
+        None => format!("{component:.6}"),
+    }
+}
+
+/// A typed view on a ReShade preset (`.ini`)
+///
+/// All changes are made on the underlying [`IniDocument`], so a preset that is
+/// read and written again keeps everything ReShader doesn't know about.
+#[derive(Debug, Clone, Default)]
+pub struct Preset {
+    document: IniDocument,
+}
+
+impl Preset {
+    /// Creates an empty preset
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the contents of a preset file
+    pub fn parse(content: &str) -> Self {
+        Self {
+            document: IniDocument::parse(content),
+        }
+    }
+
+    /// Reads and parses a preset file
+    pub fn load(path: &Path) -> ReShaderResult<Self> {
+        Ok(Self {
+            document: IniDocument::load(path)?,
+        })
+    }
+
+    /// Serializes and writes the preset file
+    pub fn save(&self, path: &Path) -> ReShaderResult<()> {
+        self.document.save(path)
+    }
+
+    /// Returns the underlying INI document
+    pub fn document(&self) -> &IniDocument {
+        &self.document
+    }
+
+    /// Returns the underlying INI document for changes the typed API doesn't cover
+    pub fn document_mut(&mut self) -> &mut IniDocument {
+        &mut self.document
+    }
+
+    /// Returns the enabled techniques in the order they are rendered
+    pub fn techniques(&self) -> Vec<Technique> {
+        self.technique_list("Techniques")
+    }
+
+    /// Replaces the enabled techniques
+    pub fn set_techniques(&mut self, techniques: &[Technique]) -> bool {
+        self.set_technique_list("Techniques", techniques)
+    }
+
+    /// Returns the order of all techniques in the overlay, including disabled ones
+    pub fn technique_sorting(&self) -> Vec<Technique> {
+        self.technique_list("TechniqueSorting")
+    }
+
+    /// Replaces the order of all techniques in the overlay
+    pub fn set_technique_sorting(&mut self, techniques: &[Technique]) -> bool {
+        self.set_technique_list("TechniqueSorting", techniques)
+    }
+
+    /// Returns the preprocessor definitions of the preset, e.g. `SMAA_PRESET_HIGH=1`
+    pub fn preprocessor_definitions(&self) -> Vec<String> {
+        self.document
+            .get(None, "PreprocessorDefinitions")
+            .map(split_list)
+            .unwrap_or_default()
+    }
+
+    /// Replaces the preprocessor definitions of the preset
+    pub fn set_preprocessor_definitions<S: AsRef<str>>(&mut self, definitions: &[S]) -> bool {
+        let value = definitions
+            .iter()
+            .map(|definition| definition.as_ref())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.document.set(None, "PreprocessorDefinitions", &value)
+    }
+
+    /// Returns the shortcut that toggles a technique
+    pub fn technique_key(&self, technique: &str) -> Option<KeyBinding> {
+        self.document
+            .get(None, &format!("Key{technique}"))?
+            .parse()
+            .ok()
+    }
+
+    /// Sets the shortcut that toggles a technique
+    pub fn set_technique_key(&mut self, technique: &str, binding: KeyBinding) -> bool {
+        self.document
+            .set(None, &format!("Key{technique}"), &binding.to_string())
+    }
+
+    /// Returns the effect files that have uniform values in this preset
+    pub fn effect_files(&self) -> Vec<&str> {
+        self.document
+            .sections()
+            .iter()
+            .filter_map(|section| section.name())
+            .collect()
+    }
+
+    /// Returns the uniform values stored for an effect file, in order
+    ///
+    /// Values that aren't numbers are skipped, they can be read through [`Preset::document`].
+    pub fn uniforms(&self, effect_file: &str) -> Vec<(String, UniformValue)> {
+        self.document
+            .section(Some(effect_file))
+            .map(|section| {
+                section
+                    .entries()
+                    .filter_map(|(name, value)| Some((name.to_string(), value.parse().ok()?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the value of a uniform of an effect file
+    pub fn uniform(&self, effect_file: &str, name: &str) -> Option<UniformValue> {
+        self.document.get(Some(effect_file), name)?.parse().ok()
+    }
+
+    /// Sets the value of a uniform of an effect file, returning whether it changed
+    ///
+    /// Components that keep their value keep their text, changed ones are written with the same number of decimals.
+    pub fn set_uniform(&mut self, effect_file: &str, name: &str, value: &UniformValue) -> bool {
+        let current = self
+            .document
+            .get(Some(effect_file), name)
+            .map(|current| current.split(',').collect::<Vec<_>>())
+            .unwrap_or_default();
+        let components = value
+            .0
+            .iter()
+            .enumerate()
+            .map(|(index, component)| match current.get(index) {
+                Some(raw) if raw.trim().parse::<f64>() == Ok(*component) => raw.to_string(),
+                Some(raw) => {
+                    let decimals = raw
+                        .trim()
+                        .split_once('.')
+                        .map(|(_, decimals)| decimals.len());
+                    format_component(*component, decimals)
+                }
+                None => format_component(*component, None),
+            })
+            .collect::<Vec<_>>();
+        self.document
+            .set(Some(effect_file), name, &components.join(","))
+    }
+
+    /// Removes a uniform of an effect file, returning whether it existed
+    pub fn remove_uniform(&mut self, effect_file: &str, name: &str) -> bool {
+        self.document.remove(Some(effect_file), name)
+    }
+
+    /// Removes all uniform values of an effect file, returning whether there were any
+    pub fn remove_effect_file(&mut self, effect_file: &str) -> bool {
+        self.document.remove_section(effect_file)
+    }
+
+    fn technique_list(&self, key: &str) -> Vec<Technique> {
+        self.document
+            .get(None, key)
+            .map(split_list)
+            .unwrap_or_default()
+            .iter()
+            .map(|technique| technique.parse().unwrap())
+            .collect()
+    }
+
+    fn set_technique_list(&mut self, key: &str, techniques: &[Technique]) -> bool {
+        let value = techniques
+            .iter()
+            .map(Technique::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        self.document.set(None, key, &value)
+    }
+}
+
+/// Returns whether the content of an INI file is a ReShade preset and not e.g. a ReShade.ini
+pub fn is_preset(content: &str) -> bool {
+    IniDocument::parse(content)
+        .get(None, "Techniques")
+        .is_some()
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    /// A preset in the format ReShade saves it in
+    const PRESET: &str = "PreprocessorDefinitions=SMAA_PRESET_HIGH=1
+Techniques=SMAA@SMAA.fx,LumaSharpen@LumaSharpen.fx,Vibrance@Vibrance.fx
+TechniqueSorting=SMAA@SMAA.fx,LumaSharpen@LumaSharpen.fx,Vibrance@Vibrance.fx,Deband@Deband.fx
+KeyLumaSharpen=46,0,1,0
+
+[LumaSharpen.fx]
+offset_bias=1.000000
+pattern=1
+sharp_clamp=0.035000
+sharp_strength=0.650000
+show_sharpen=0
+
+[SMAA.fx]
+CornerRounding=25
+DepthEdgeDetectionThreshold=0.010000
+EdgeDetectionThreshold=0.100000
+EdgeDetectionType=1
+MaxSearchSteps=32
+MaxSearchStepsDiagonal=16
+PredicationEnabled=0
+PredicationScale=2.000000
+PredicationStrength=0.400000
+PredicationThreshold=0.010000
+
+[Vibrance.fx]
+Vibrance=0.150000
+VibranceRGBBalance=1.000000,1.000000,1.000000
+";
+
+    fn round_trip(edit: impl FnOnce(&mut Preset)) -> String {
+        let tmp = TempDir::new("reshader-preset").unwrap();
+        let path = tmp.path().join("Preset.ini");
+        std::fs::write(&path, PRESET).unwrap();
+
+        let mut preset = Preset::load(&path).unwrap();
+        edit(&mut preset);
+        preset.save(&path).unwrap();
+        std::fs::read_to_string(&path).unwrap()
+    }
+
+    #[test]
+    fn unchanged_preset_round_trips() {
+        assert_eq!(round_trip(|_| {}), PRESET);
+    }
+
+    #[test]
+    fn setting_a_uniform_only_changes_the_changed_component() {
+        let written = round_trip(|preset| {
+            let mut value = preset.uniform("Vibrance.fx", "VibranceRGBBalance").unwrap();
+            value.0[1] = 1.25;
+            assert!(preset.set_uniform("Vibrance.fx", "VibranceRGBBalance", &value));
+        });
+
+        assert_eq!(
+            written,
+            PRESET.replace(
+                "VibranceRGBBalance=1.000000,1.000000,1.000000",
+                "VibranceRGBBalance=1.000000,1.250000,1.000000"
+            )
+        );
+    }
+
+    #[test]
+    fn setting_the_same_value_changes_nothing() {
+        let written = round_trip(|preset| {
+            let value = preset.uniform("SMAA.fx", "PredicationScale").unwrap();
+            assert!(!preset.set_uniform("SMAA.fx", "PredicationScale", &value));
+        });
+
+        assert_eq!(written, PRESET);
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::HashSet,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    prelude::*,
+    preset::{is_preset, Preset},
+    reshade_ini::{backup, wine_path, ReShadeIni},
+};
+
+/// The name of the directory presets are stored in, inside the data directory
+pub const PRESET_STORE_DIR: &str = "presets";
+
+/// The presets imported into the data directory
+#[derive(Debug, Clone)]
+pub struct PresetStore {
+    root: PathBuf,
+}
+
+/// A preset in the [`PresetStore`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredPreset {
+    /// The path of the preset relative to the store without the extension, e.g. `FFXIV/Bloom`
+    pub name: String,
+    /// The location of the preset file
+    pub path: PathBuf,
+}
+
+impl PresetStore {
+    /// Opens the preset store of a data directory, creating it if necessary
+    pub fn open(data_dir: &Path) -> ReShaderResult<Self> {
+        let root = data_dir.join(PRESET_STORE_DIR);
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Returns the directory the presets are stored in
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Imports the presets from a preset file, a zip file or a directory
+    ///
+    /// Presets from zip files and directories are stored in a folder named after them.
+    /// INI files that aren't presets, like ReShade.ini, are skipped. Existing presets are replaced.
+    pub fn import(&self, source: &Path) -> ReShaderResult<Vec<StoredPreset>> {
+        let stem = source
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let mut files = Vec::new();
+
+        if source.is_dir() {
+            let mut paths = Vec::new();
+            list_ini_files(source, &mut HashSet::new(), &mut paths)?;
+            for path in paths {
+                let relative = path.strip_prefix(source).unwrap().to_path_buf();
+                files.push((Path::new(&stem).join(relative), std::fs::read(path)?));
+            }
+        } else if is_extension(source, "zip") {
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(source)?)?;
+            let mut entries = Vec::new();
+            for i in 0..archive.len() {
+                let mut entry = archive.by_index(i)?;
+                let Some(path) = entry.enclosed_name().map(Path::to_path_buf) else {
+                    continue;
+                };
+                if !entry.is_file() || !is_extension(&path, "ini") {
+                    continue;
+                }
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content)?;
+                entries.push((path, content));
+            }
+
+            // zips downloaded from GitHub wrap everything in a single folder
+            let root = common_root(entries.iter().map(|(path, _)| path.as_path()));
+            for (path, content) in entries {
+                let relative = match &root {
+                    Some(root) => path.strip_prefix(root).unwrap().to_path_buf(),
+                    None => path,
+                };
+                files.push((Path::new(&stem).join(relative), content));
+            }
+        } else {
+            let file_name = source.file_name().unwrap_or_default();
+            files.push((PathBuf::from(file_name), std::fs::read(source)?));
+        }
+
+        let mut imported = Vec::new();
+        for (relative, content) in files {
+            if !is_preset(&String::from_utf8_lossy(&content)) {
+                continue;
+            }
+
+            let path = self.root.join(&relative);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(&path, content)?;
+            imported.push(self.stored(path));
+        }
+
+        Ok(imported)
+    }
+
+    /// Returns all presets in the store, sorted by name
+    pub fn list(&self) -> ReShaderResult<Vec<StoredPreset>> {
+        let mut paths = Vec::new();
+        list_ini_files(&self.root, &mut HashSet::new(), &mut paths)?;
+
+        let mut presets = paths
+            .into_iter()
+            .map(|path| self.stored(path))
+            .collect::<Vec<_>>();
+        presets.sort_by_key(|preset| preset.name.to_lowercase());
+        Ok(presets)
+    }
+
+    /// Finds presets whose name or enabled techniques contain the term, ignoring case
+    pub fn search(&self, term: &str) -> ReShaderResult<Vec<StoredPreset>> {
+        let term = term.to_lowercase();
+        let mut found = Vec::new();
+
+        for preset in self.list()? {
+            let matches = preset.name.to_lowercase().contains(&term)
+                || Preset::load(&preset.path)?
+                    .techniques()
+                    .iter()
+                    .any(|technique| technique.name.to_lowercase().contains(&term));
+            if matches {
+                found.push(preset);
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Finds a preset by its full name or, if that is unambiguous, by its file name alone
+    pub fn find(&self, name: &str) -> ReShaderResult<StoredPreset> {
+        let name = name.trim_end_matches(".ini").replace('\\', "/");
+        let presets = self.list()?;
+
+        if let Some(preset) = presets
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(&name))
+        {
+            return Ok(preset.clone());
+        }
+
+        let mut candidates = presets.into_iter().filter(|preset| {
+            preset
+                .name
+                .rsplit('/')
+                .next()
+                .is_some_and(|file| file.eq_ignore_ascii_case(&name))
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(preset), None) => Ok(preset),
+            (Some(_), Some(_)) => Err(ReShaderError::AmbiguousPreset(name)),
+            _ => Err(ReShaderError::PresetNotFound(name)),
+        }
+    }
+
+    /// Copies a preset out of the store
+    ///
+    /// If the destination is a directory, the preset keeps its file name.
+    pub fn export(&self, preset: &StoredPreset, destination: &Path) -> ReShaderResult<PathBuf> {
+        let destination = if destination.is_dir() {
+            destination.join(preset.path.file_name().unwrap())
+        } else {
+            destination.to_path_buf()
+        };
+        std::fs::copy(&preset.path, &destination)?;
+        Ok(destination)
+    }
+
+    fn stored(&self, path: PathBuf) -> StoredPreset {
+        let name = path
+            .strip_prefix(&self.root)
+            .unwrap()
+            .with_extension("")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        StoredPreset { name, path }
+    }
+}
+
+/// Makes a game load a preset by setting `PresetPath` in its ReShade.ini
+///
+/// The ReShade.ini is backed up before it is changed for the first time.
+/// Returns whether the ReShade.ini changed.
+pub fn apply_preset(preset_path: &Path, game_path: &Path) -> ReShaderResult<bool> {
+    let ini_path = game_path.join("ReShade.ini");
+    let mut reshade_ini = if ini_path.exists() {
+        ReShadeIni::load(&ini_path)?
+    } else {
+        ReShadeIni::default_config()
+    };
+
+    let preset_path = preset_path.canonicalize()?;
+    if !reshade_ini.set_preset_path(&wine_path(&preset_path)) && ini_path.exists() {
+        return Ok(false);
+    }
+
+    backup(&ini_path)?;
+    reshade_ini.save(&ini_path)?;
+    Ok(true)
+}
+
+fn is_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+/// Returns the folder all paths are inside of, if there is exactly one
+fn common_root<'a>(paths: impl Iterator<Item = &'a Path>) -> Option<PathBuf> {
+    let mut root = None;
+    for path in paths {
+        let mut components = path.components();
+        let first = match (components.next(), components.next()) {
+            (Some(Component::Normal(first)), Some(_)) => PathBuf::from(first),
+            _ => return None,
+        };
+        match &root {
+            Some(root) if *root != first => return None,
+            Some(_) => {}
+            None => root = Some(first),
+        }
+    }
+    root
+}
+
+/// Lists the INI files inside a directory, following symlinks but visiting every directory only once
+fn list_ini_files(
+    directory: &Path,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> ReShaderResult<()> {
+    // a symlink to a parent directory would otherwise be followed forever
+    if !directory.is_dir() || !visited.insert(directory.canonicalize()?) {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            list_ini_files(&path, visited, files)?;
+        } else if is_extension(&path, "ini") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn import_survives_symlink_loops() {
+        let tmp = TempDir::new("reshader-preset-store-loop").unwrap();
+        let source = tmp.path().join("Presets");
+        std::fs::create_dir_all(source.join("FFXIV")).unwrap();
+        std::fs::write(
+            source.join("FFXIV").join("Bloom.ini"),
+            "Techniques=Bloom@Bloom.fx\n",
+        )
+        .unwrap();
+        std::os::unix::fs::symlink("..", source.join("FFXIV").join("Parent")).unwrap();
+        let store = PresetStore::open(&tmp.path().join("data")).unwrap();
+
+        let imported = store.import(&source).unwrap();
+
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].name, "Presets/FFXIV/Bloom");
+        assert_eq!(store.list().unwrap(), imported);
+    }
+}
@@ -0,0 +1,424 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::prelude::*;
+
+/// The name of the directory the install receipts are stored in, inside the data directory
+pub const RECEIPTS_DIR: &str = "receipts";
+
+/// Everything ReShader created inside a game directory
+///
+/// Uninstalling removes exactly the paths listed here, and only if they haven't been changed since.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// The game directory
+    pub game_path: PathBuf,
+    /// The paths created by ReShader, in the order they were created
+    pub entries: Vec<ReceiptEntry>,
+}
+
+/// A path created by ReShader
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptEntry {
+    /// The location of the path
+    pub path: PathBuf,
+    /// What was created
+    pub kind: EntryKind,
+    /// Where a symlink points to
+    pub target: Option<PathBuf>,
+    /// The SHA-256 hash of a file, of the file a symlink points to, or of everything inside a directory when it was created
+    pub hash: Option<String>,
+    /// The file or directory in the data directory this path was created from
+    #[serde(default)]
+    pub source: Option<PathBuf>,
+    /// Where the file that was at this path before has been moved to, it is moved back on uninstall
+    #[serde(default)]
+    pub replaced: Option<PathBuf>,
+    /// Whether uninstalling leaves the path alone, e.g. `ReShade.ini` as it holds the settings of the user
+    #[serde(default)]
+    pub keep: bool,
+}
+
+/// The type of a [`ReceiptEntry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    /// A symbolic link
+    Symlink,
+    /// A regular file
+    File,
+    /// A directory
+    Directory,
+}
+
+/// How a path compares to its [`ReceiptEntry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryState {
+    /// The path is still as ReShader created it
+    Unchanged,
+    /// The path has been replaced or modified since ReShader created it
+    Changed,
+    /// The path doesn't exist anymore
+    Missing,
+}
+
+impl Receipt {
+    /// Returns where the receipt for a game is stored
+    pub fn path(data_dir: &Path, game_path: &Path) -> PathBuf {
+        // ignore differences like trailing slashes
+        let game_path = game_path.components().collect::<PathBuf>();
+        let hash = Sha256::digest(game_path.to_string_lossy().as_bytes());
+        data_dir
+            .join(RECEIPTS_DIR)
+            .join(format!("{}.json", &hex(&hash)[..16]))
+    }
+
+    /// Reads the receipt of a game, returning an empty receipt if there is none
+    pub fn load(data_dir: &Path, game_path: &Path) -> ReShaderResult<Self> {
+        let path = Self::path(data_dir, game_path);
+        if !path.exists() {
+            return Ok(Self {
+                game_path: game_path.to_path_buf(),
+                entries: Vec::new(),
+            });
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| ReShaderError::Receipt(e.to_string()))
+    }
+
+    /// Reads the receipts of all games, including games that aren't registered (yet)
+    pub fn load_all(data_dir: &Path) -> ReShaderResult<Vec<Self>> {
+        let directory = data_dir.join(RECEIPTS_DIR);
+        if !directory.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut receipts = Vec::new();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let content = std::fs::read_to_string(path)?;
+                receipts.push(
+                    serde_json::from_str(&content)
+                        .map_err(|e| ReShaderError::Receipt(e.to_string()))?,
+                );
+            }
+        }
+        Ok(receipts)
+    }
+
+    /// Returns whether a receipt has been written for a game
+    pub fn exists(data_dir: &Path, game_path: &Path) -> bool {
+        Self::path(data_dir, game_path).exists()
+    }
+
+    /// Writes the receipt, or deletes it if it has no entries left
+    pub fn save(&self, data_dir: &Path) -> ReShaderResult<()> {
+        let path = Self::path(data_dir, &self.game_path);
+        if self.entries.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| ReShaderError::Receipt(e.to_string()))?;
+        crate::write_atomically(&path, content.as_bytes())
+    }
+
+    /// Records a path ReShader just created, replacing an older entry for the same path
+    pub fn record(&mut self, path: &Path) -> ReShaderResult<()> {
+        let metadata = std::fs::symlink_metadata(path)?;
+        let entry = if metadata.file_type().is_symlink() {
+            ReceiptEntry {
+                path: path.to_path_buf(),
+                kind: EntryKind::Symlink,
+                target: Some(std::fs::read_link(path)?),
+                hash: if path.is_file() {
+                    Some(hash_file(path)?)
+                } else {
+                    None
+                },
+                source: None,
+                replaced: None,
+                keep: false,
+            }
+        } else if metadata.is_dir() {
+            ReceiptEntry {
+                path: path.to_path_buf(),
+                kind: EntryKind::Directory,
+                target: None,
+                hash: Some(hash_directory(path)?),
+                source: None,
+                replaced: None,
+                keep: false,
+            }
+        } else {
+            ReceiptEntry {
+                path: path.to_path_buf(),
+                kind: EntryKind::File,
+                target: None,
+                hash: Some(hash_file(path)?),
+                source: None,
+                replaced: None,
+                keep: false,
+            }
+        };
+
+        // reinstalling must not lose track of the original file
+        let replaced = self.entry(path).and_then(|entry| entry.replaced.clone());
+        self.forget(path);
+        self.entries.push(ReceiptEntry { replaced, ..entry });
+        Ok(())
+    }
+
+    /// Records a path ReShader just created from a file or directory in the data directory, see [`Self::record`]
+    pub fn record_link(&mut self, path: &Path, source: &Path) -> ReShaderResult<()> {
+        self.record(path)?;
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.path == path) {
+            entry.source = Some(source.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Records a path ReShader just created that uninstalling leaves alone, see [`ReceiptEntry::keep`]
+    pub fn record_kept(&mut self, path: &Path) -> ReShaderResult<()> {
+        self.record(path)?;
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.path == path) {
+            entry.keep = true;
+        }
+        Ok(())
+    }
+
+    /// Records that the file previously at a recorded path has been moved to `original`
+    pub fn record_replaced(&mut self, path: &Path, original: &Path) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.path == path) {
+            entry.replaced = Some(original.to_path_buf());
+        }
+    }
+
+    /// Removes the entry for a path
+    pub fn forget(&mut self, path: &Path) {
+        self.entries.retain(|entry| entry.path != path);
+    }
+
+    /// Returns the entry for a path
+    pub fn entry(&self, path: &Path) -> Option<&ReceiptEntry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+}
+
+impl ReceiptEntry {
+    /// Compares the path to what ReShader created
+    ///
+    /// Symlinks only have to point to the same target, as updating ReShade changes the files they point to.
+    /// For the same reason, files and directories may also match the file or directory they were created from.
+    /// Directories recorded without a hash are always considered changed, as files may have been added to them.
+    pub fn state(&self) -> ReShaderResult<EntryState> {
+        let Ok(metadata) = std::fs::symlink_metadata(&self.path) else {
+            return Ok(EntryState::Missing);
+        };
+
+        let unchanged = match self.kind {
+            EntryKind::Symlink => {
+                metadata.file_type().is_symlink()
+                    && std::fs::read_link(&self.path).ok() == self.target
+            }
+            EntryKind::Directory if metadata.is_dir() => {
+                let hash = hash_directory(&self.path)?;
+                Some(&hash) == self.hash.as_ref()
+                    || match &self.source {
+                        Some(source) if source.is_dir() => hash_directory(source)? == hash,
+                        _ => false,
+                    }
+            }
+            EntryKind::Directory => false,
+            EntryKind::File if metadata.is_file() => {
+                let hash = hash_file(&self.path)?;
+                Some(&hash) == self.hash.as_ref()
+                    || match &self.source {
+                        Some(source) if source.is_file() => hash_file(source)? == hash,
+                        _ => false,
+                    }
+            }
+            EntryKind::File => false,
+        };
+
+        Ok(if unchanged {
+            EntryState::Unchanged
+        } else {
+            EntryState::Changed
+        })
+    }
+}
+
+/// Returns the SHA-256 hash of a file as a hex string
+pub fn hash_file(path: &Path) -> ReShaderResult<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Returns the SHA-256 hash of the names, symlink targets and file contents inside a directory as a hex string
+pub fn hash_directory(path: &Path) -> ReShaderResult<String> {
+    let mut hasher = Sha256::new();
+    hash_directory_into(path, Path::new(""), &mut hasher)?;
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hash_directory_into(root: &Path, relative: &Path, hasher: &mut Sha256) -> ReShaderResult<()> {
+    let mut entries = std::fs::read_dir(root.join(relative))?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let relative = relative.join(entry.file_name());
+        let path = root.join(&relative);
+        hasher.update(relative.to_string_lossy().as_bytes());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            hasher.update(b"\0symlink\0");
+            hasher.update(std::fs::read_link(&path)?.to_string_lossy().as_bytes());
+        } else if file_type.is_dir() {
+            hasher.update(b"\0directory\0");
+            hash_directory_into(root, &relative, hasher)?;
+        } else {
+            hasher.update(b"\0file\0");
+            hasher.update(hash_file(&path)?.as_bytes());
+        }
+    }
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::LinkMode;
+    use tempdir::TempDir;
+
+    #[test]
+    fn record_link_and_replaced_survive_reinstalls() {
+        let tmp = TempDir::new("reshader-receipt-record").unwrap();
+        let source = tmp.path().join("ReShade64.dll");
+        let path = tmp.path().join("dxgi.dll");
+        let original = tmp.path().join("dxgi.reshader-original.dll");
+        std::fs::write(&source, "reshade").unwrap();
+        std::os::unix::fs::symlink(&source, &path).unwrap();
+
+        let mut receipt = Receipt::default();
+        receipt.record_link(&path, &source).unwrap();
+        receipt.record_replaced(&path, &original);
+        let entry = receipt.entry(&path).unwrap();
+        assert_eq!(entry.kind, EntryKind::Symlink);
+        assert_eq!(entry.target.as_deref(), Some(source.as_path()));
+        assert_eq!(entry.source.as_deref(), Some(source.as_path()));
+        assert_eq!(entry.replaced.as_deref(), Some(original.as_path()));
+
+        // recording the path again must not lose the original file
+        receipt.record_link(&path, &source).unwrap();
+        assert_eq!(receipt.entries.len(), 1);
+        assert_eq!(
+            receipt.entry(&path).unwrap().replaced.as_deref(),
+            Some(original.as_path())
+        );
+
+        receipt.forget(&path);
+        assert!(receipt.entry(&path).is_none());
+        assert!(receipt.entries.is_empty());
+    }
+
+    #[test]
+    fn state_detects_changed_files() {
+        let tmp = TempDir::new("reshader-receipt-state").unwrap();
+        let file = tmp.path().join("ReShade.ini");
+        let directory = tmp.path().join("reshade-shaders");
+        std::fs::write(&file, "[GENERAL]").unwrap();
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("Shader.fx"), "shader").unwrap();
+
+        let mut receipt = Receipt::default();
+        receipt.record(&file).unwrap();
+        receipt.record(&directory).unwrap();
+        let file_entry = receipt.entry(&file).unwrap();
+        let directory_entry = receipt.entry(&directory).unwrap();
+        assert_eq!(file_entry.state().unwrap(), EntryState::Unchanged);
+        assert_eq!(directory_entry.state().unwrap(), EntryState::Unchanged);
+
+        std::fs::write(&file, "[GENERAL]\nchanged").unwrap();
+        std::fs::write(directory.join("Other.fx"), "added").unwrap();
+        assert_eq!(file_entry.state().unwrap(), EntryState::Changed);
+        assert_eq!(directory_entry.state().unwrap(), EntryState::Changed);
+
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(file_entry.state().unwrap(), EntryState::Missing);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let tmp = TempDir::new("reshader-receipt-save").unwrap();
+        let data_dir = tmp.path().join("data");
+        let game_path = tmp.path().join("game");
+        std::fs::create_dir_all(&game_path).unwrap();
+        std::fs::write(game_path.join("ReShade.ini"), "").unwrap();
+
+        let mut receipt = Receipt::load(&data_dir, &game_path).unwrap();
+        receipt.record_kept(&game_path.join("ReShade.ini")).unwrap();
+        receipt.save(&data_dir).unwrap();
+        assert_eq!(Receipt::load(&data_dir, &game_path).unwrap(), receipt);
+        assert_eq!(Receipt::load_all(&data_dir).unwrap(), vec![receipt.clone()]);
+
+        receipt.forget(&game_path.join("ReShade.ini"));
+        receipt.save(&data_dir).unwrap();
+        assert!(!Receipt::exists(&data_dir, &game_path));
+    }
+
+    #[test]
+    fn uninstall_leaves_foreign_and_changed_files_alone() {
+        let tmp = TempDir::new("reshader-receipt-uninstall").unwrap();
+        let data_dir = tmp.path().join("data");
+        let game_path = tmp.path().join("game");
+        let source = data_dir.join("Merged");
+        std::fs::create_dir_all(source.join("Shaders")).unwrap();
+        std::fs::create_dir_all(&game_path).unwrap();
+        std::fs::write(game_path.join("save.dat"), "save").unwrap();
+        crate::install_reshade_shaders(&data_dir, &source, &game_path, LinkMode::Copy).unwrap();
+        let shaders = game_path.join("reshade-shaders");
+        assert!(Receipt::load(&data_dir, &game_path)
+            .unwrap()
+            .entry(&shaders)
+            .is_some());
+
+        // the user adds a shader to the copied directory
+        std::fs::write(shaders.join("Shaders").join("Mine.fx"), "mine").unwrap();
+        let kept = crate::uninstall(&data_dir, &game_path).unwrap();
+
+        assert_eq!(kept, vec![shaders.clone()]);
+        assert!(shaders.join("Shaders").join("Mine.fx").exists());
+        assert_eq!(
+            std::fs::read_to_string(game_path.join("save.dat")).unwrap(),
+            "save"
+        );
+        assert!(!Receipt::exists(&data_dir, &game_path));
+    }
+}
@@ -0,0 +1,595 @@
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use strum::{Display as StrumDisplay, EnumIter, EnumString};
+
+use crate::{ini_file::IniDocument, prelude::*, DEFAULT_INI};
+
+/// The suffix of the backup ReShader creates before modifying an existing ReShade.ini
+pub const BACKUP_SUFFIX: &str = "reshader-backup";
+
+/// A section of ReShade.ini
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, EnumString, StrumDisplay)]
+#[strum(serialize_all = "UPPERCASE", ascii_case_insensitive)]
+pub enum Section {
+    /// Settings that change how the game itself behaves (`[APP]`)
+    App,
+    /// Settings for add-ons (`[ADDON]`)
+    Addon,
+    /// Settings for depth buffer detection (`[DEPTH]`)
+    Depth,
+    /// General settings like search paths and presets (`[GENERAL]`)
+    General,
+    /// Keyboard shortcuts (`[INPUT]`)
+    Input,
+    /// Settings for the overlay (`[OVERLAY]`)
+    Overlay,
+    /// Loading another DLL with the same name as ReShade, e.g. DXVK (`[PROXY]`)
+    Proxy,
+    /// Settings for screenshots (`[SCREENSHOT]`)
+    Screenshot,
+    /// Colors and fonts of the overlay (`[STYLE]`)
+    Style,
+}
+
+/// The type of a value in ReShade.ini
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// `0` or `1`
+    Bool,
+    /// A whole number
+    Integer,
+    /// A decimal number
+    Float,
+    /// Free text, e.g. a file name pattern
+    String,
+    /// A single path
+    Path,
+    /// A comma separated list, e.g. search paths or preprocessor definitions
+    List,
+    /// A keyboard shortcut, see [`KeyBinding`]
+    KeyBinding,
+}
+
+/// A key ReShade knows about
+#[derive(Debug, Clone, Copy)]
+pub struct KnownKey {
+    /// The section the key belongs to
+    pub section: Section,
+    /// The name of the key
+    pub key: &'static str,
+    /// The type of the value
+    pub kind: ValueKind,
+}
+
+const fn known(section: Section, key: &'static str, kind: ValueKind) -> KnownKey {
+    KnownKey { section, key, kind }
+}
+
+/// The keys ReShade reads from ReShade.ini
+#[rustfmt::skip]
+pub const KNOWN_KEYS: &[KnownKey] = &[
+    known(Section::Addon, "AddonPath", ValueKind::Path),
+    known(Section::Addon, "DisabledAddons", ValueKind::List),
+    known(Section::App, "Force10BitFormat", ValueKind::Bool),
+    known(Section::App, "ForceDefaultRefreshRate", ValueKind::Bool),
+    known(Section::App, "ForceFullscreen", ValueKind::Bool),
+    known(Section::App, "ForceResolution", ValueKind::List),
+    known(Section::App, "ForceVsync", ValueKind::Bool),
+    known(Section::App, "ForceWindowed", ValueKind::Bool),
+    known(Section::Depth, "DepthCopyAtClearIndex", ValueKind::Integer),
+    known(Section::Depth, "DepthCopyBeforeClears", ValueKind::Bool),
+    known(Section::Depth, "UseAspectRatioHeuristics", ValueKind::Bool),
+    known(Section::General, "EffectSearchPaths", ValueKind::List),
+    known(Section::General, "IntermediateCachePath", ValueKind::Path),
+    known(Section::General, "NoDebugInfo", ValueKind::Bool),
+    known(Section::General, "NoEffectCache", ValueKind::Bool),
+    known(Section::General, "NoReloadOnInit", ValueKind::Bool),
+    known(Section::General, "PerformanceMode", ValueKind::Bool),
+    known(Section::General, "PreprocessorDefinitions", ValueKind::List),
+    known(Section::General, "PresetPath", ValueKind::Path),
+    known(Section::General, "PresetTransitionDuration", ValueKind::Integer),
+    known(Section::General, "SkipLoadingDisabledEffects", ValueKind::Bool),
+    known(Section::General, "StartupPresetPath", ValueKind::Path),
+    known(Section::General, "TextureSearchPaths", ValueKind::List),
+    known(Section::Input, "ForceShortcutModifiers", ValueKind::Bool),
+    known(Section::Input, "GamepadNavigation", ValueKind::Bool),
+    known(Section::Input, "InputProcessing", ValueKind::Integer),
+    known(Section::Input, "KeyEffects", ValueKind::KeyBinding),
+    known(Section::Input, "KeyNextPreset", ValueKind::KeyBinding),
+    known(Section::Input, "KeyOverlay", ValueKind::KeyBinding),
+    known(Section::Input, "KeyPerformanceMode", ValueKind::KeyBinding),
+    known(Section::Input, "KeyPreviousPreset", ValueKind::KeyBinding),
+    known(Section::Input, "KeyReload", ValueKind::KeyBinding),
+    known(Section::Input, "KeyScreenshot", ValueKind::KeyBinding),
+    known(Section::Overlay, "AutoSavePreset", ValueKind::Bool),
+    known(Section::Overlay, "ClockFormat", ValueKind::Integer),
+    known(Section::Overlay, "FPSPosition", ValueKind::Integer),
+    known(Section::Overlay, "NoFontScaling", ValueKind::Bool),
+    known(Section::Overlay, "SaveWindowState", ValueKind::Bool),
+    known(Section::Overlay, "ShowClock", ValueKind::Bool),
+    known(Section::Overlay, "ShowForceLoadEffectsButton", ValueKind::Bool),
+    known(Section::Overlay, "ShowFPS", ValueKind::Bool),
+    known(Section::Overlay, "ShowFrameTime", ValueKind::Bool),
+    known(Section::Overlay, "ShowPresetTransitionMessage", ValueKind::Bool),
+    known(Section::Overlay, "ShowScreenshotMessage", ValueKind::Bool),
+    known(Section::Overlay, "TutorialProgress", ValueKind::Integer),
+    known(Section::Overlay, "VariableListHeight", ValueKind::Float),
+    known(Section::Overlay, "VariableListUseTabs", ValueKind::Bool),
+    known(Section::Proxy, "EnableProxyLibrary", ValueKind::Bool),
+    known(Section::Proxy, "ProxyLibrary", ValueKind::Path),
+    known(Section::Screenshot, "ClearAlpha", ValueKind::Bool),
+    known(Section::Screenshot, "FileFormat", ValueKind::Integer),
+    known(Section::Screenshot, "FileNaming", ValueKind::String),
+    known(Section::Screenshot, "JPEGQuality", ValueKind::Integer),
+    known(Section::Screenshot, "PlaySound", ValueKind::Bool),
+    known(Section::Screenshot, "PostSaveCommand", ValueKind::Path),
+    known(Section::Screenshot, "SaveBeforeShot", ValueKind::Bool),
+    known(Section::Screenshot, "SaveOverlayShot", ValueKind::Bool),
+    known(Section::Screenshot, "SavePath", ValueKind::Path),
+    known(Section::Screenshot, "SavePresetFile", ValueKind::Bool),
+    known(Section::Screenshot, "SoundPath", ValueKind::Path),
+    known(Section::Style, "Alpha", ValueKind::Float),
+    known(Section::Style, "ChildRounding", ValueKind::Float),
+    known(Section::Style, "EditorFont", ValueKind::Path),
+    known(Section::Style, "EditorFontSize", ValueKind::Integer),
+    known(Section::Style, "EditorStyleIndex", ValueKind::Integer),
+    known(Section::Style, "Font", ValueKind::Path),
+    known(Section::Style, "FontSize", ValueKind::Integer),
+    known(Section::Style, "FrameRounding", ValueKind::Float),
+    known(Section::Style, "GrabRounding", ValueKind::Float),
+    known(Section::Style, "PopupRounding", ValueKind::Float),
+    known(Section::Style, "ScrollbarRounding", ValueKind::Float),
+    known(Section::Style, "StyleIndex", ValueKind::Integer),
+    known(Section::Style, "TabRounding", ValueKind::Float),
+    known(Section::Style, "WindowRounding", ValueKind::Float),
+];
+
+/// Returns the definition of a key ReShade knows about
+///
+/// Keys are compared case-insensitively, so the returned key contains the canonical spelling.
+pub fn known_key(section: Section, key: &str) -> Option<&'static KnownKey> {
+    KNOWN_KEYS
+        .iter()
+        .find(|known| known.section == section && known.key.eq_ignore_ascii_case(key))
+}
+
+/// A keyboard shortcut as stored in ReShade.ini, e.g. `36,0,0,0` for the Home key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    /// The Windows virtual key code
+    pub key_code: u32,
+    /// Whether Ctrl has to be held
+    pub ctrl: bool,
+    /// Whether Shift has to be held
+    pub shift: bool,
+    /// Whether Alt has to be held
+    pub alt: bool,
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .split(',')
+            .map(|part| part.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("\"{s}\" is not a list of numbers"))?;
+
+        match parts.as_slice() {
+            [key_code, ctrl, shift, alt] => Ok(Self {
+                key_code: *key_code,
+                ctrl: *ctrl != 0,
+                shift: *shift != 0,
+                alt: *alt != 0,
+            }),
+            [key_code] => Ok(Self {
+                key_code: *key_code,
+                ctrl: false,
+                shift: false,
+                alt: false,
+            }),
+            _ => Err(format!("\"{s}\" must have 1 or 4 numbers")),
+        }
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{},{}",
+            self.key_code, self.ctrl as u8, self.shift as u8, self.alt as u8
+        )
+    }
+}
+
+/// A typed view on a ReShade.ini file
+///
+/// All changes are made on the underlying [`IniDocument`], so comments and unknown keys are kept.
+#[derive(Debug, Clone)]
+pub struct ReShadeIni {
+    document: IniDocument,
+}
+
+impl ReShadeIni {
+    /// Parses the contents of a ReShade.ini file
+    pub fn parse(content: &str) -> Self {
+        Self {
+            document: IniDocument::parse(content),
+        }
+    }
+
+    /// Returns the ReShade.ini ReShader installs into games without one
+    pub fn default_config() -> Self {
+        Self::parse(DEFAULT_INI)
+    }
+
+    /// Reads and parses a ReShade.ini file
+    pub fn load(path: &Path) -> ReShaderResult<Self> {
+        Ok(Self {
+            document: IniDocument::load(path)?,
+        })
+    }
+
+    /// Serializes and writes the ReShade.ini file
+    pub fn save(&self, path: &Path) -> ReShaderResult<()> {
+        self.document.save(path)
+    }
+
+    /// Returns the underlying INI document
+    pub fn document(&self) -> &IniDocument {
+        &self.document
+    }
+
+    /// Returns the underlying INI document for changes the typed API doesn't cover
+    pub fn document_mut(&mut self) -> &mut IniDocument {
+        &mut self.document
+    }
+
+    /// Returns the raw value of a key
+    pub fn get_string(&self, section: Section, key: &str) -> Option<&str> {
+        self.document.get(Some(&section.to_string()), key)
+    }
+
+    /// Sets the raw value of a key, returning whether it changed
+    pub fn set_string(&mut self, section: Section, key: &str, value: &str) -> bool {
+        self.document.set(Some(&section.to_string()), key, value)
+    }
+
+    /// Removes a key, returning whether it existed
+    pub fn remove(&mut self, section: Section, key: &str) -> bool {
+        self.document.remove(Some(&section.to_string()), key)
+    }
+
+    /// Returns the value of a boolean key
+    pub fn get_bool(&self, section: Section, key: &str) -> Option<bool> {
+        match self.get_string(section, key)? {
+            "1" | "true" => Some(true),
+            "0" | "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Sets the value of a boolean key, returning whether it changed
+    pub fn set_bool(&mut self, section: Section, key: &str, value: bool) -> bool {
+        self.set_string(section, key, if value { "1" } else { "0" })
+    }
+
+    /// Returns the value of a numeric key
+    pub fn get_integer(&self, section: Section, key: &str) -> Option<i64> {
+        self.get_string(section, key)?.parse().ok()
+    }
+
+    /// Returns the entries of a list key in order, e.g. the search paths
+    pub fn get_list(&self, section: Section, key: &str) -> Vec<String> {
+        self.get_string(section, key)
+            .map(split_list)
+            .unwrap_or_default()
+    }
+
+    /// Replaces the entries of a list key, returning whether it changed
+    pub fn set_list<S: AsRef<str>>(&mut self, section: Section, key: &str, values: &[S]) -> bool {
+        let value = values
+            .iter()
+            .map(|value| value.as_ref())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.set_string(section, key, &value)
+    }
+
+    /// Appends an entry to a list key unless it's already in the list, returning whether it changed
+    pub fn add_to_list(&mut self, section: Section, key: &str, value: &str) -> bool {
+        let mut list = self.get_list(section, key);
+        if list.iter().any(|entry| same_entry(entry, value)) {
+            return false;
+        }
+        list.push(value.to_string());
+        self.set_list(section, key, &list)
+    }
+
+    /// Removes an entry from a list key, returning whether it changed
+    pub fn remove_from_list(&mut self, section: Section, key: &str, value: &str) -> bool {
+        let mut list = self.get_list(section, key);
+        let before = list.len();
+        list.retain(|entry| !same_entry(entry, value));
+        if list.len() == before {
+            return false;
+        }
+        self.set_list(section, key, &list)
+    }
+
+    /// Appends all entries missing from a list key, keeping the order of the existing entries
+    ///
+    /// Returns whether the list changed.
+    pub fn merge_list<S: AsRef<str>>(&mut self, section: Section, key: &str, values: &[S]) -> bool {
+        let mut changed = false;
+        for value in values {
+            changed |= self.add_to_list(section, key, value.as_ref());
+        }
+        changed
+    }
+
+    /// Returns the value of a keyboard shortcut key
+    pub fn get_key_binding(&self, section: Section, key: &str) -> Option<KeyBinding> {
+        self.get_string(section, key)?.parse().ok()
+    }
+
+    /// Sets the value of a keyboard shortcut key, returning whether it changed
+    pub fn set_key_binding(&mut self, section: Section, key: &str, binding: KeyBinding) -> bool {
+        self.set_string(section, key, &binding.to_string())
+    }
+
+    /// Returns the paths ReShade searches for effect files, in order
+    pub fn effect_search_paths(&self) -> Vec<String> {
+        self.get_list(Section::General, "EffectSearchPaths")
+    }
+
+    /// Returns the paths ReShade searches for textures, in order
+    pub fn texture_search_paths(&self) -> Vec<String> {
+        self.get_list(Section::General, "TextureSearchPaths")
+    }
+
+    /// Returns the global preprocessor definitions, e.g. `RESHADE_DEPTH_INPUT_IS_REVERSED=1`
+    pub fn preprocessor_definitions(&self) -> Vec<String> {
+        self.get_list(Section::General, "PreprocessorDefinitions")
+    }
+
+    /// Returns the path to the active preset
+    pub fn preset_path(&self) -> Option<&str> {
+        self.get_string(Section::General, "PresetPath")
+    }
+
+    /// Sets the path to the active preset, returning whether it changed
+    pub fn set_preset_path(&mut self, path: &str) -> bool {
+        self.set_string(Section::General, "PresetPath", path)
+    }
+
+    /// Makes ReShade load another DLL, e.g. the `dxgi.dll` of DXVK it replaced
+    ///
+    /// Returns whether the settings changed.
+    pub fn set_proxy_library(&mut self, library: &str) -> bool {
+        let enabled = self.set_bool(Section::Proxy, "EnableProxyLibrary", true);
+        self.set_string(Section::Proxy, "ProxyLibrary", library) || enabled
+    }
+
+    /// Returns the directory screenshots are saved to
+    pub fn screenshot_path(&self) -> Option<&str> {
+        self.get_string(Section::Screenshot, "SavePath")
+    }
+
+    /// Returns the shortcut that opens the overlay
+    pub fn overlay_key(&self) -> Option<KeyBinding> {
+        self.get_key_binding(Section::Input, "KeyOverlay")
+    }
+
+    /// Sets a preprocessor definition, replacing an existing definition with the same name
+    ///
+    /// Returns whether the definitions changed.
+    pub fn set_preprocessor_definition(&mut self, name: &str, value: &str) -> bool {
+        let definition = format!("{name}={value}");
+        let mut definitions = self.preprocessor_definitions();
+        match definitions
+            .iter_mut()
+            .find(|existing| definition_name(existing) == name)
+        {
+            Some(existing) if *existing == definition => return false,
+            Some(existing) => *existing = definition,
+            None => definitions.push(definition),
+        }
+        self.set_list(Section::General, "PreprocessorDefinitions", &definitions)
+    }
+
+    /// Removes the preprocessor definition with the given name, returning whether it existed
+    pub fn remove_preprocessor_definition(&mut self, name: &str) -> bool {
+        let mut definitions = self.preprocessor_definitions();
+        let before = definitions.len();
+        definitions.retain(|definition| definition_name(definition) != name);
+        if definitions.len() == before {
+            return false;
+        }
+        self.set_list(Section::General, "PreprocessorDefinitions", &definitions)
+    }
+}
+
+fn definition_name(definition: &str) -> &str {
+    definition
+        .split_once('=')
+        .map_or(definition, |(name, _)| name)
+        .trim()
+}
+
+impl Display for ReShadeIni {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.document)
+    }
+}
+
+/// Splits a list value of ReShade.ini into its entries
+pub fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Compares two list entries the way Windows would compare paths
+fn same_entry(a: &str, b: &str) -> bool {
+    a.replace('/', "\\")
+        .eq_ignore_ascii_case(&b.replace('/', "\\"))
+}
+
+/// A search path of ReShade.ini translated to a Linux path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPath {
+    /// The directory to search
+    pub path: PathBuf,
+    /// Whether subdirectories are searched as well (a trailing `**` or `**\*`)
+    pub recursive: bool,
+}
+
+/// Translates an entry of `EffectSearchPaths` or `TextureSearchPaths` to a Linux path
+///
+/// Relative entries are resolved against the game directory and `Z:` is mapped to `/` like Wine does.
+/// Entries on other drives can't be resolved without the Wine prefix and return `None`.
+pub fn resolve_search_path(game_path: &Path, entry: &str) -> Option<SearchPath> {
+    let mut entry = entry.trim().replace('\\', "/");
+    // strip the wildcard components first, `**`, `**/*` and `*` can all end an entry
+    let mut recursive = false;
+    loop {
+        entry = entry.trim_end_matches('/').to_string();
+        let (rest, last) = entry.rsplit_once('/').unwrap_or(("", &entry));
+        match last {
+            "**" => recursive = true,
+            "*" => {}
+            _ => break,
+        }
+        entry = rest.to_string();
+    }
+
+    let path = match entry.as_bytes() {
+        [drive, b':', ..] if drive.eq_ignore_ascii_case(&b'z') => {
+            PathBuf::from("/").join(entry[2..].trim_start_matches('/'))
+        }
+        [_, b':', ..] => return None,
+        _ if entry.starts_with('/') => PathBuf::from(&entry),
+        _ => game_path.join(entry.trim_start_matches("./")),
+    };
+
+    Some(SearchPath { path, recursive })
+}
+
+/// Translates an absolute Linux path to the path Wine shows it as on the `Z:` drive
+pub fn wine_path(path: &Path) -> String {
+    format!("Z:{}", path.to_string_lossy().replace('/', "\\"))
+}
+
+/// Returns the path of the backup ReShader creates before modifying a file
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(BACKUP_SUFFIX);
+    path.with_file_name(file_name)
+}
+
+/// Backs up a file before ReShader modifies it for the first time
+///
+/// An existing backup is never overwritten, so it always contains the original file.
+pub fn backup(path: &Path) -> ReShaderResult<()> {
+    let backup = backup_path(path);
+    if path.exists() && !backup.exists() {
+        std::fs::copy(path, backup)?;
+    }
+    Ok(())
+}
+
+/// Checks whether a key is known to ReShade and the value has the type ReShade expects
+pub fn validate_value(
+    section: Section,
+    key: &str,
+    value: &str,
+) -> ReShaderResult<&'static KnownKey> {
+    let known = known_key(section, key)
+        .ok_or_else(|| ReShaderError::UnknownIniKey(section.to_string(), key.to_string()))?;
+    let invalid = |message: &str| {
+        ReShaderError::InvalidIniValue(
+            section.to_string(),
+            known.key.to_string(),
+            message.to_string(),
+        )
+    };
+
+    match known.kind {
+        ValueKind::Bool if value != "0" && value != "1" => Err(invalid("expected 0 or 1")),
+        ValueKind::Integer if value.parse::<i64>().is_err() => {
+            Err(invalid("expected a whole number"))
+        }
+        ValueKind::Float if value.parse::<f64>().is_err() => Err(invalid("expected a number")),
+        ValueKind::KeyBinding => value
+            .parse::<KeyBinding>()
+            .map(|_| known)
+            .map_err(|message| invalid(&message)),
+        _ => Ok(known),
+    }
+}
+
+/// Checks whether a key is known to ReShade and contains a list
+pub fn validate_list_key(section: Section, key: &str) -> ReShaderResult<&'static KnownKey> {
+    let known = known_key(section, key)
+        .ok_or_else(|| ReShaderError::UnknownIniKey(section.to_string(), key.to_string()))?;
+    if known.kind != ValueKind::List {
+        return Err(ReShaderError::InvalidIniValue(
+            section.to_string(),
+            known.key.to_string(),
+            "not a list".to_string(),
+        ));
+    }
+    Ok(known)
+}
+
+/// Makes sure the ReShade.ini at the given path contains the search paths ReShader needs
+///
+/// If the file doesn't exist, the default ReShade.ini is written. Otherwise the missing
+/// `EffectSearchPaths` and `TextureSearchPaths` are appended while keeping everything else.
+/// Before an existing file is modified for the first time, it is backed up next to itself.
+///
+/// Returns whether the file changed.
+pub fn merge_reshade_ini(ini_path: &Path) -> ReShaderResult<bool> {
+    let Some(ini) = merged_reshade_ini(ini_path)? else {
+        return Ok(false);
+    };
+
+    backup(ini_path)?;
+    ini.save(ini_path)?;
+
+    Ok(true)
+}
+
+/// Returns the ReShade.ini [`merge_reshade_ini`] would write, or `None` if it doesn't have to change
+pub fn merged_reshade_ini(ini_path: &Path) -> ReShaderResult<Option<ReShadeIni>> {
+    if !ini_path.exists() {
+        return Ok(Some(ReShadeIni::default_config()));
+    }
+
+    let defaults = ReShadeIni::default_config();
+    let mut ini = ReShadeIni::load(ini_path)?;
+
+    let effect_changed = ini.merge_list(
+        Section::General,
+        "EffectSearchPaths",
+        &defaults.effect_search_paths(),
+    );
+    let texture_changed = ini.merge_list(
+        Section::General,
+        "TextureSearchPaths",
+        &defaults.texture_search_paths(),
+    );
+
+    Ok((effect_changed || texture_changed).then_some(ini))
+}
@@ -0,0 +1,245 @@
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    executable::read_file_version,
+    prelude::*,
+    receipt::{hash_file, EntryState, Receipt},
+    reshade_ini::{resolve_search_path, ReShadeIni},
+    LINKED_PATHS,
+};
+
+/// The DLL names ReShade can be loaded as, the first one found in a game directory is reported
+const PROXY_DLLS: [&str; 6] = [
+    "dxgi.dll",
+    "d3d9.dll",
+    "d3d11.dll",
+    "d3d12.dll",
+    "opengl32.dll",
+    "dinput8.dll",
+];
+
+/// What is installed for a game, see [`game_status`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameStatus {
+    /// The game directory
+    pub game_path: PathBuf,
+    /// The file version of the installed ReShade DLL
+    pub version: Option<String>,
+    /// Which ReShade build is installed
+    pub variant: Option<Variant>,
+    /// The name ReShade is loaded as, e.g. `dxgi.dll`
+    pub proxy: Option<String>,
+    /// Which shaders are installed
+    pub shaders: ShaderSet,
+    /// The directory the game loads presets from
+    pub preset_folder: Option<PathBuf>,
+    /// The state of the paths ReShader created
+    pub links: LinkHealth,
+    /// When ReShader last changed the game directory, as a Unix timestamp
+    pub updated: Option<u64>,
+}
+
+/// A build of ReShade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    /// ReShade with support for addons
+    Addon,
+    /// ReShade without support for addons
+    Vanilla,
+}
+
+/// The shaders installed in a game directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShaderSet {
+    /// No shaders
+    None,
+    /// The ReShade shader collections (`reshade-shaders`)
+    ReShade,
+    /// The GShade shaders (`gshade-shaders`)
+    GShade,
+    /// Both the ReShade and the GShade shaders
+    Both,
+}
+
+/// How many of the paths ReShader created are still intact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LinkHealth {
+    /// The number of paths ReShader created
+    pub total: usize,
+    /// The number of paths that are missing, dangling or have been changed
+    pub broken: usize,
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Variant::Addon => write!(f, "addon"),
+            Variant::Vanilla => write!(f, "vanilla"),
+        }
+    }
+}
+
+impl Display for ShaderSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderSet::None => write!(f, "none"),
+            ShaderSet::ReShade => write!(f, "ReShade"),
+            ShaderSet::GShade => write!(f, "GShade"),
+            ShaderSet::Both => write!(f, "ReShade + GShade"),
+        }
+    }
+}
+
+impl Display for LinkHealth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.broken == 0 {
+            write!(f, "ok ({})", self.total)
+        } else {
+            write!(f, "{} of {} broken", self.broken, self.total)
+        }
+    }
+}
+
+/// Inspects a game directory to find out what ReShader installed there
+pub fn game_status(data_dir: &Path, game_path: &Path) -> ReShaderResult<GameStatus> {
+    let receipt = Receipt::load(data_dir, game_path)?;
+
+    let mut proxy = None;
+    let mut variant = None;
+    for name in PROXY_DLLS {
+        if let Some(found) = reshade_variant(data_dir, &receipt, &game_path.join(name))? {
+            proxy = Some(name.to_string());
+            variant = Some(found);
+            break;
+        }
+    }
+    let version = match &proxy {
+        Some(proxy) if game_path.join(proxy).is_file() => {
+            read_file_version(&game_path.join(proxy))?
+        }
+        _ => None,
+    };
+
+    let shaders = match (
+        game_path.join("reshade-shaders").is_dir(),
+        game_path.join("gshade-shaders").is_dir(),
+    ) {
+        (false, false) => ShaderSet::None,
+        (true, false) => ShaderSet::ReShade,
+        (false, true) => ShaderSet::GShade,
+        (true, true) => ShaderSet::Both,
+    };
+
+    let ini_path = game_path.join("ReShade.ini");
+    let preset_path = if ini_path.exists() {
+        ReShadeIni::load(&ini_path)?
+            .preset_path()
+            .and_then(|preset_path| resolve_search_path(game_path, preset_path))
+            .and_then(|preset_path| preset_path.path.parent().map(Path::to_path_buf))
+    } else {
+        None
+    };
+    let gshade_presets = game_path.join("gshade-presets");
+    let preset_folder = preset_path.or_else(|| gshade_presets.is_dir().then_some(gshade_presets));
+
+    Ok(GameStatus {
+        game_path: game_path.to_path_buf(),
+        version,
+        variant,
+        proxy,
+        shaders,
+        preset_folder,
+        links: link_health(game_path, &receipt)?,
+        updated: last_update(data_dir, game_path),
+    })
+}
+
+/// Returns which ReShade build a DLL is, or `None` if it isn't one ReShader installed
+fn reshade_variant(
+    data_dir: &Path,
+    receipt: &Receipt,
+    path: &Path,
+) -> ReShaderResult<Option<Variant>> {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return Ok(None);
+    };
+
+    let source = if metadata.file_type().is_symlink() {
+        std::fs::read_link(path).ok()
+    } else {
+        receipt.entry(path).and_then(|entry| entry.source.clone())
+    };
+    let name = match source.as_ref().and_then(|source| source.file_name()) {
+        Some(name) => name.to_string_lossy().to_string(),
+        None if path.is_file() => {
+            // a copy installed before sources were recorded
+            let hash = hash_file(path)?;
+            let mut found = String::new();
+            for name in ["ReShade64.Addon.dll", "ReShade64.Vanilla.dll"] {
+                let source = data_dir.join(name);
+                if source.is_file() && hash_file(&source)? == hash {
+                    found = name.to_string();
+                }
+            }
+            found
+        }
+        None => String::new(),
+    };
+
+    Ok(match name.as_str() {
+        "ReShade64.Addon.dll" => Some(Variant::Addon),
+        "ReShade64.Vanilla.dll" => Some(Variant::Vanilla),
+        _ => None,
+    })
+}
+
+fn link_health(game_path: &Path, receipt: &Receipt) -> ReShaderResult<LinkHealth> {
+    let mut health = LinkHealth {
+        total: 0,
+        broken: 0,
+    };
+
+    // kept files like ReShade.ini are expected to change
+    for entry in receipt.entries.iter().filter(|entry| !entry.keep) {
+        health.total += 1;
+        let dangling = std::fs::symlink_metadata(&entry.path).is_ok() && !entry.path.exists();
+        if dangling || entry.state()? != EntryState::Unchanged {
+            health.broken += 1;
+        }
+    }
+
+    // games installed before receipts existed only have the known symlinks
+    if receipt.entries.is_empty() {
+        for name in LINKED_PATHS {
+            let path = game_path.join(name);
+            if std::fs::read_link(&path).is_ok() {
+                health.total += 1;
+                if !path.exists() {
+                    health.broken += 1;
+                }
+            }
+        }
+    }
+
+    Ok(health)
+}
+
+/// Returns when the receipt of the game was last written, or the ReShade DLL was linked for older installations
+fn last_update(data_dir: &Path, game_path: &Path) -> Option<u64> {
+    [
+        Receipt::path(data_dir, game_path),
+        game_path.join("dxgi.dll"),
+    ]
+    .iter()
+    .find_map(|path| std::fs::symlink_metadata(path).ok()?.modified().ok())
+    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+    .map(|duration| duration.as_secs())
+}
@@ -0,0 +1,344 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// The name of the directory the journals of unfinished transactions are kept in, inside the data directory
+pub const JOURNAL_DIR: &str = "journal";
+
+/// Changes to the filesystem that are undone unless they are committed
+///
+/// Every step is written to a journal in the data directory before it is made,
+/// so a transaction that was interrupted is rolled back by [`recover`] on the next start.
+#[derive(Debug)]
+pub struct Transaction {
+    path: PathBuf,
+    journal: Journal,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Journal {
+    /// Set once all changes have been made, recovering then only cleans up
+    committed: bool,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "kebab-case")]
+enum Step {
+    /// `path` is about to be created and is removed on rollback
+    Created { path: PathBuf },
+    /// `path` has been moved aside to `staged`, which is moved back on rollback and removed on commit
+    Staged { path: PathBuf, staged: PathBuf },
+    /// `from` is about to be renamed to `to`
+    Moved { from: PathBuf, to: PathBuf },
+    /// The content of a small file before the transaction, `None` if it didn't exist
+    Saved {
+        path: PathBuf,
+        content: Option<Vec<u8>>,
+    },
+}
+
+impl Transaction {
+    /// Starts a transaction, its journal is kept in the given data directory
+    pub fn begin(data_dir: &Path) -> ReShaderResult<Self> {
+        let directory = data_dir.join(JOURNAL_DIR);
+        std::fs::create_dir_all(&directory)?;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let transaction = Self {
+            path: directory.join(format!("{nanos}-{}.json", std::process::id())),
+            journal: Journal::default(),
+        };
+        transaction.write()?;
+        Ok(transaction)
+    }
+
+    /// Records that `path` is about to be created, so it is removed on rollback
+    ///
+    /// Fails if `path` already exists, as rolling back would remove it.
+    pub fn create(&mut self, path: &Path) -> ReShaderResult<()> {
+        if exists(path) {
+            return Err(std::io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            )
+            .into());
+        }
+        self.push(Step::Created {
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Moves `path` out of the way, it is moved back on rollback and removed on commit
+    pub fn stage(&mut self, path: &Path) -> ReShaderResult<()> {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(format!(".reshader-undo-{}", self.journal.steps.len()));
+        let staged = path.with_file_name(file_name);
+
+        self.push(Step::Staged {
+            path: path.to_path_buf(),
+            staged: staged.clone(),
+        })?;
+        std::fs::rename(path, staged)?;
+        Ok(())
+    }
+
+    /// Renames `from` to `to`, it is renamed back on rollback
+    pub fn rename(&mut self, from: &Path, to: &Path) -> ReShaderResult<()> {
+        self.push(Step::Moved {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        })?;
+        std::fs::rename(from, to)?;
+        Ok(())
+    }
+
+    /// Remembers the content of a small file like a receipt or a ReShade.ini, so it is restored on rollback
+    pub fn save(&mut self, path: &Path) -> ReShaderResult<()> {
+        let content = if path.exists() {
+            Some(std::fs::read(path)?)
+        } else {
+            None
+        };
+        self.push(Step::Saved {
+            path: path.to_path_buf(),
+            content,
+        })
+    }
+
+    /// Keeps the changes and removes the paths that were moved out of the way
+    pub fn commit(mut self) -> ReShaderResult<()> {
+        self.journal.committed = true;
+        self.write()?;
+        self.journal.clean_up()?;
+        std::fs::remove_file(&self.path)?;
+        Ok(())
+    }
+
+    /// Undoes the changes in reverse order
+    pub fn rollback(self) -> ReShaderResult<()> {
+        self.journal.roll_back()?;
+        std::fs::remove_file(&self.path)?;
+        Ok(())
+    }
+
+    /// Runs `f`, committing the transaction if it succeeds and rolling it back if it fails
+    ///
+    /// If rolling back fails as well, both errors are returned, see [`ReShaderError::Rollback`].
+    pub fn run<T>(
+        data_dir: &Path,
+        f: impl FnOnce(&mut Transaction) -> ReShaderResult<T>,
+    ) -> ReShaderResult<T> {
+        let mut transaction = Self::begin(data_dir)?;
+        match f(&mut transaction) {
+            Ok(value) => {
+                transaction.commit()?;
+                Ok(value)
+            }
+            Err(e) => match transaction.rollback() {
+                Ok(()) => Err(e),
+                Err(rollback) => Err(ReShaderError::Rollback(Box::new(e), Box::new(rollback))),
+            },
+        }
+    }
+
+    fn push(&mut self, step: Step) -> ReShaderResult<()> {
+        self.journal.steps.push(step);
+        self.write()
+    }
+
+    fn write(&self) -> ReShaderResult<()> {
+        let content = serde_json::to_string_pretty(&self.journal)
+            .map_err(|e| ReShaderError::Journal(e.to_string()))?;
+        // replacing the journal in one step makes sure it is never half written
+        let temporary = self.path.with_extension("tmp");
+        std::fs::write(&temporary, content)?;
+        std::fs::rename(temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Journal {
+    fn clean_up(&self) -> ReShaderResult<()> {
+        for step in &self.steps {
+            if let Step::Staged { staged, .. } = step {
+                remove(staged)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Every step may or may not have happened when the journal was written, so each undo checks first
+    fn roll_back(&self) -> ReShaderResult<()> {
+        for step in self.steps.iter().rev() {
+            match step {
+                Step::Created { path } => remove(path)?,
+                Step::Staged { path, staged } => {
+                    if exists(staged) {
+                        remove(path)?;
+                        std::fs::rename(staged, path)?;
+                    }
+                }
+                Step::Moved { from, to } => {
+                    if exists(to) && !exists(from) {
+                        std::fs::rename(to, from)?;
+                    }
+                }
+                Step::Saved { path, content } => match content {
+                    Some(content) => std::fs::write(path, content)?,
+                    None => remove(path)?,
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Finishes or rolls back the transactions that were interrupted, returning how many there were
+pub fn recover(data_dir: &Path) -> ReShaderResult<usize> {
+    let directory = data_dir.join(JOURNAL_DIR);
+    if !directory.is_dir() {
+        return Ok(0);
+    }
+
+    let mut recovered = 0;
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "tmp") {
+            std::fs::remove_file(path)?;
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let journal = serde_json::from_str::<Journal>(&content)
+            .map_err(|e| ReShaderError::Journal(e.to_string()))?;
+        if journal.committed {
+            journal.clean_up()?;
+        } else {
+            journal.roll_back()?;
+        }
+        std::fs::remove_file(path)?;
+        recovered += 1;
+    }
+
+    Ok(recovered)
+}
+
+fn exists(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok()
+}
+
+fn remove(path: &Path) -> ReShaderResult<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(path)?,
+        Ok(_) => std::fs::remove_file(path)?,
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn failure_in_the_middle_rolls_back() {
+        let tmp = TempDir::new("reshader-transaction-failure").unwrap();
+        let directory = tmp.path();
+        let kept = directory.join("kept");
+        let saved = directory.join("saved");
+        let created = directory.join("created");
+        std::fs::write(&kept, "kept").unwrap();
+        std::fs::write(&saved, [0xff, 0x00, 0xfe]).unwrap();
+
+        let result = Transaction::run(directory, |transaction| {
+            transaction.stage(&kept)?;
+            transaction.save(&saved)?;
+            std::fs::write(&saved, "changed")?;
+            transaction.create(&created)?;
+            std::fs::write(&created, "created")?;
+            Err::<(), _>(ReShaderError::ReadZipFile)
+        });
+
+        assert!(matches!(result, Err(ReShaderError::ReadZipFile)));
+        assert_eq!(std::fs::read_to_string(&kept).unwrap(), "kept");
+        assert_eq!(std::fs::read(&saved).unwrap(), [0xff, 0x00, 0xfe]);
+        assert!(!created.exists());
+        assert_eq!(
+            std::fs::read_dir(directory.join(JOURNAL_DIR))
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn create_refuses_existing_paths() {
+        let tmp = TempDir::new("reshader-transaction-create").unwrap();
+        let directory = tmp.path();
+        let existing = directory.join("existing");
+        std::fs::write(&existing, "existing").unwrap();
+
+        let result = Transaction::run(directory, |transaction| transaction.create(&existing));
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "existing");
+    }
+
+    #[test]
+    fn recover_rolls_back_interrupted_transactions() {
+        let tmp = TempDir::new("reshader-transaction-recover").unwrap();
+        let directory = tmp.path();
+        let staged = directory.join("staged");
+        let moved = directory.join("moved");
+        let created = directory.join("created");
+        std::fs::write(&staged, "staged").unwrap();
+        std::fs::write(&moved, "moved").unwrap();
+
+        let mut transaction = Transaction::begin(directory).unwrap();
+        transaction.stage(&staged).unwrap();
+        transaction
+            .rename(&moved, &directory.join("renamed"))
+            .unwrap();
+        transaction.create(&created).unwrap();
+        std::fs::write(&created, "created").unwrap();
+        // the process is interrupted before the transaction is committed
+        drop(transaction);
+
+        assert_eq!(recover(directory).unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(&staged).unwrap(), "staged");
+        assert_eq!(std::fs::read_to_string(&moved).unwrap(), "moved");
+        assert!(!directory.join("renamed").exists());
+        assert!(!created.exists());
+        assert_eq!(recover(directory).unwrap(), 0);
+    }
+
+    #[test]
+    fn recover_finishes_committed_transactions() {
+        let tmp = TempDir::new("reshader-transaction-committed").unwrap();
+        let directory = tmp.path();
+        let replaced = directory.join("replaced");
+        std::fs::write(&replaced, "old").unwrap();
+
+        let mut transaction = Transaction::begin(directory).unwrap();
+        transaction.stage(&replaced).unwrap();
+        std::fs::write(&replaced, "new").unwrap();
+        transaction.journal.committed = true;
+        transaction.write().unwrap();
+        // the process is interrupted while the staged paths are removed
+        drop(transaction);
+
+        assert_eq!(recover(directory).unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(&replaced).unwrap(), "new");
+        assert_eq!(std::fs::read_dir(directory).unwrap().count(), 2);
+    }
+}
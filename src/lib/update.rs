@@ -0,0 +1,344 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::GameEntry,
+    download_reshade, download_shader_collections,
+    executable::read_file_version,
+    install_preset_for_game, install_reshade_shaders, minimal_collection_names,
+    plan_install_reshade_from,
+    prelude::*,
+    receipt::hash_directory,
+    reshade_version_dir,
+    status::{game_status, GameStatus, ShaderSet, Variant},
+    SHADER_COLLECTIONS,
+};
+
+/// A part of a game [`update_games`] refreshes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateStep {
+    /// The ReShade DLL
+    ReShade,
+    /// The ReShade shader collections
+    Shaders,
+    /// The GShade presets and shaders
+    Presets,
+}
+
+/// What updating a part of a game did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    /// The part was updated, with a description of the change
+    Updated(String),
+    /// The part already was up to date
+    Unchanged(String),
+    /// The part has nothing to be updated from, with the reason
+    Skipped(String),
+    /// The part could not be updated, with the reason
+    Failed(String),
+}
+
+/// What [`update_games`] did for a game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameUpdate {
+    /// The game directory
+    pub game_path: PathBuf,
+    /// The parts that are installed for the game and what happened to them
+    pub steps: Vec<(UpdateStep, UpdateOutcome)>,
+}
+
+impl GameUpdate {
+    /// Returns whether any part could not be updated
+    pub fn failed(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|(_, outcome)| matches!(outcome, UpdateOutcome::Failed(_)))
+    }
+
+    /// Returns whether any part was updated
+    pub fn changed(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|(_, outcome)| matches!(outcome, UpdateOutcome::Updated(_)))
+    }
+}
+
+impl Display for UpdateStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateStep::ReShade => write!(f, "ReShade"),
+            UpdateStep::Shaders => write!(f, "Shaders"),
+            UpdateStep::Presets => write!(f, "Presets"),
+        }
+    }
+}
+
+/// Updates ReShade, the shader collections and the GShade presets of games
+///
+/// Only the parts that are installed for a game are updated. Every ReShade version and variant the games need is
+/// downloaded once: the latest one to the data directory, and the ones games are pinned to to their
+/// [`reshade_version_dir`]. The shader collections of all games are downloaded together.
+/// Afterwards, the files are linked into every game again with its own link mode.
+///
+/// A part that fails doesn't keep the other parts or games from being updated.
+pub async fn update_games(
+    client: &reqwest::Client,
+    data_dir: &Path,
+    games: &[GameEntry],
+    specific_installer: &Option<String>,
+) -> Vec<GameUpdate> {
+    let statuses = games
+        .iter()
+        .map(|game| game_status(data_dir, &game.path).map_err(|e| e.to_string()))
+        .collect::<Vec<_>>();
+
+    let mut reshade_dirs = HashMap::new();
+    for (game, status) in games.iter().zip(&statuses) {
+        let Ok(GameStatus {
+            variant: Some(variant),
+            ..
+        }) = status
+        else {
+            continue;
+        };
+        let key = (game.pinned_version.clone(), *variant);
+        if let Entry::Vacant(entry) = reshade_dirs.entry(key) {
+            let reshade_dir = download_variant(
+                client,
+                data_dir,
+                game.pinned_version.as_deref(),
+                *variant,
+                specific_installer,
+            )
+            .await
+            .map_err(|e| e.to_string());
+            entry.insert(reshade_dir);
+        }
+    }
+
+    let has_shaders =
+        |status: &Result<GameStatus, String>| status.as_ref().is_ok_and(has_reshade_shaders);
+    let mut collection_names = games
+        .iter()
+        .zip(&statuses)
+        .filter(|(_, status)| has_shaders(status))
+        .flat_map(|(game, _)| game.collections.iter().cloned())
+        .collect::<Vec<_>>();
+    collection_names.sort();
+    collection_names.dedup();
+    if collection_names.is_empty() {
+        // games installed before the collections were recorded
+        collection_names = minimal_collection_names();
+    }
+    let shaders = if statuses.iter().any(has_shaders) {
+        let collections = SHADER_COLLECTIONS
+            .iter()
+            .filter(|collection| collection_names.contains(&collection.name))
+            .collect::<Vec<_>>();
+        // the merged shaders are compared to find out whether the download brought anything new
+        let merged_directory = data_dir.join("Merged");
+        let merged_hash = || hash_directory(&merged_directory).ok();
+        let before = merged_hash();
+        Some(
+            download_shader_collections(&collections, data_dir)
+                .await
+                .map(|_| before.is_none() || merged_hash() != before)
+                .map_err(|e| e.to_string()),
+        )
+    } else {
+        None
+    };
+
+    let mut updates = Vec::new();
+    for (game, status) in games.iter().zip(statuses) {
+        let mut update = GameUpdate {
+            game_path: game.path.clone(),
+            steps: Vec::new(),
+        };
+        let status = match status {
+            Ok(status) => status,
+            Err(e) => {
+                update
+                    .steps
+                    .push((UpdateStep::ReShade, UpdateOutcome::Failed(e)));
+                updates.push(update);
+                continue;
+            }
+        };
+
+        if let Some(variant) = status.variant {
+            let outcome = match &reshade_dirs[&(game.pinned_version.clone(), variant)] {
+                Ok(reshade_dir) => update_reshade(data_dir, reshade_dir, game, &status, variant),
+                Err(e) => UpdateOutcome::Failed(e.clone()),
+            };
+            update.steps.push((UpdateStep::ReShade, outcome));
+        }
+
+        if let Some(shaders) = shaders.as_ref().filter(|_| has_reshade_shaders(&status)) {
+            let outcome = match shaders {
+                Ok(changed) => {
+                    match install_reshade_shaders(
+                        data_dir,
+                        &data_dir.join("Merged"),
+                        &game.path,
+                        game.link_mode,
+                    ) {
+                        Ok(()) if *changed => {
+                            UpdateOutcome::Updated("new shaders downloaded".to_string())
+                        }
+                        Ok(()) => UpdateOutcome::Unchanged("up to date".to_string()),
+                        Err(e) => UpdateOutcome::Failed(e.to_string()),
+                    }
+                }
+                Err(e) => UpdateOutcome::Failed(e.clone()),
+            };
+            update.steps.push((UpdateStep::Shaders, outcome));
+        }
+
+        if game.path.join("gshade-presets").exists() {
+            // GShade isn't developed anymore, the presets only change when a download is installed again
+            let no_source = "no source to update from, install a newer download with `reshader install-presets`";
+            let outcome = if game.link_mode.is_symlink() {
+                UpdateOutcome::Skipped(no_source.to_string())
+            } else {
+                // copies and hard links keep the files from the time they were made
+                match install_preset_for_game(data_dir, &game.path, game.link_mode) {
+                    Ok(()) => UpdateOutcome::Skipped(format!(
+                        "{no_source} (linked the installed presets again)"
+                    )),
+                    Err(e) => UpdateOutcome::Failed(e.to_string()),
+                }
+            };
+            update.steps.push((UpdateStep::Presets, outcome));
+        }
+
+        updates.push(update);
+    }
+
+    updates
+}
+
+/// Downloads a ReShade variant, returning the directory it was downloaded to
+///
+/// Pinned versions that have been downloaded before are not downloaded again.
+async fn download_variant(
+    client: &reqwest::Client,
+    data_dir: &Path,
+    pinned_version: Option<&str>,
+    variant: Variant,
+    specific_installer: &Option<String>,
+) -> ReShaderResult<PathBuf> {
+    let vanilla = variant == Variant::Vanilla;
+    match pinned_version {
+        Some(version) => {
+            let reshade_dir = reshade_version_dir(data_dir, version);
+            if !reshade_dir.join(dll_name(variant)).exists() {
+                std::fs::create_dir_all(&reshade_dir)?;
+                download_reshade(
+                    client,
+                    &reshade_dir,
+                    vanilla,
+                    Some(version.to_string()),
+                    &None,
+                )
+                .await?;
+            }
+            Ok(reshade_dir)
+        }
+        None => {
+            download_reshade(client, data_dir, vanilla, None, specific_installer).await?;
+            Ok(data_dir.to_path_buf())
+        }
+    }
+}
+
+fn update_reshade(
+    data_dir: &Path,
+    reshade_dir: &Path,
+    game: &GameEntry,
+    status: &GameStatus,
+    variant: Variant,
+) -> UpdateOutcome {
+    let dll = game.path.join("dxgi.dll");
+    let result = plan_install_reshade_from(
+        data_dir,
+        reshade_dir,
+        &game.path,
+        variant == Variant::Vanilla,
+        false,
+        game.link_mode,
+    )
+    .and_then(|plan| plan.execute(data_dir))
+    .and_then(|()| read_file_version(&dll));
+
+    let unknown = || "unknown version".to_string();
+    match result {
+        Ok(version) if version == status.version => {
+            let version = version.unwrap_or_else(unknown);
+            match &game.pinned_version {
+                Some(_) => UpdateOutcome::Unchanged(format!("{version} (pinned)")),
+                None => UpdateOutcome::Unchanged(version),
+            }
+        }
+        Ok(version) => UpdateOutcome::Updated(format!(
+            "{} -> {}",
+            status.version.clone().unwrap_or_else(unknown),
+            version.unwrap_or_else(unknown)
+        )),
+        Err(e) => UpdateOutcome::Failed(e.to_string()),
+    }
+}
+
+fn has_reshade_shaders(status: &GameStatus) -> bool {
+    matches!(status.shaders, ShaderSet::ReShade | ShaderSet::Both)
+}
+
+fn dll_name(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Addon => "ReShade64.Addon.dll",
+        Variant::Vanilla => "ReShade64.Vanilla.dll",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, link::LinkMode};
+    use tempdir::TempDir;
+
+    #[tokio::test]
+    async fn presets_are_linked_again_with_the_link_mode_of_the_game() {
+        let tmp = TempDir::new("reshader-update-link-mode").unwrap();
+        let data_dir = tmp.path().join("data");
+        let game_path = tmp.path().join("game");
+        std::fs::create_dir_all(data_dir.join("reshade-presets")).unwrap();
+        std::fs::create_dir_all(data_dir.join("reshade-shaders")).unwrap();
+        std::fs::write(data_dir.join("reshade-presets").join("Preset.ini"), "").unwrap();
+        std::fs::create_dir_all(&game_path).unwrap();
+        install_preset_for_game(&data_dir, &game_path, LinkMode::Copy).unwrap();
+        // only linking again with the link mode of the game brings the copy back
+        std::fs::remove_dir_all(game_path.join("gshade-shaders")).unwrap();
+
+        let mut config = Config::default();
+        let game = config.register_game(&game_path);
+        game.link_mode = LinkMode::Copy;
+        let updates = update_games(
+            &reqwest::Client::new(),
+            &data_dir,
+            std::slice::from_ref(game),
+            &None,
+        )
+        .await;
+
+        assert_eq!(updates.len(), 1);
+        assert!(matches!(
+            updates[0].steps.as_slice(),
+            [(UpdateStep::Presets, UpdateOutcome::Skipped(_))]
+        ));
+        let shaders = std::fs::symlink_metadata(game_path.join("gshade-shaders")).unwrap();
+        assert!(shaders.is_dir());
+    }
+}
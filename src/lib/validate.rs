@@ -0,0 +1,297 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    effect::{parse_effect, EffectInfo},
+    prelude::*,
+    preset::{Preset, Technique},
+    reshade_ini::{resolve_search_path, ReShadeIni, SearchPath},
+};
+
+/// A problem that keeps a preset from working in a game, see [`validate_preset`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresetIssue {
+    /// An effect file used by the preset is not in any effect search path
+    MissingEffect(String),
+    /// No effect file in the search paths declares the technique
+    MissingTechnique(Technique),
+    /// A texture loaded by an effect of the preset is not in any texture search path
+    MissingTexture {
+        /// The effect file loading the texture
+        effect: String,
+        /// The image file, as written in the effect
+        texture: String,
+    },
+    /// An effect file used by the preset exists in more than one search path
+    DuplicateEffect {
+        /// The file name of the effect
+        effect: String,
+        /// Every path the effect file was found at
+        paths: Vec<PathBuf>,
+    },
+    /// The preset has a value for a uniform the effect file doesn't declare
+    UnknownUniform {
+        /// The effect file the value is stored for
+        effect: String,
+        /// The name of the uniform
+        uniform: String,
+    },
+}
+
+impl Display for PresetIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PresetIssue::MissingEffect(effect) => write!(f, "effect file {effect} not found"),
+            PresetIssue::MissingTechnique(technique) => {
+                write!(f, "technique {technique} not found")
+            }
+            PresetIssue::MissingTexture { effect, texture } => {
+                write!(f, "texture {texture} used by {effect} not found")
+            }
+            PresetIssue::DuplicateEffect { effect, paths } => {
+                let paths = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>();
+                write!(
+                    f,
+                    "effect file {effect} found more than once: {}",
+                    paths.join(", ")
+                )
+            }
+            PresetIssue::UnknownUniform { effect, uniform } => {
+                write!(f, "uniform {uniform} is not declared in {effect}")
+            }
+        }
+    }
+}
+
+/// An effect file found in the effect search paths
+struct FoundEffect {
+    paths: Vec<PathBuf>,
+    info: EffectInfo,
+}
+
+/// Checks a preset against the shaders and textures a game would load
+///
+/// The search paths are read from the ReShade.ini of the game, or ReShader's default
+/// configuration if there is none. Effects are matched by file name ignoring case, like on Windows.
+pub fn validate_preset(preset: &Preset, game_path: &Path) -> ReShaderResult<Vec<PresetIssue>> {
+    let ini_path = game_path.join("ReShade.ini");
+    let reshade_ini = if ini_path.exists() {
+        ReShadeIni::load(&ini_path)?
+    } else {
+        ReShadeIni::default_config()
+    };
+
+    let effects = find_effects(game_path, &reshade_ini.effect_search_paths())?;
+    let textures = find_textures(game_path, &reshade_ini.texture_search_paths())?;
+    let mut issues = Vec::new();
+
+    // effect files referenced by techniques or by uniform values, in order of appearance
+    let mut used = Vec::<String>::new();
+    let mut use_effect = |file: &str| {
+        if !used.iter().any(|used| used.eq_ignore_ascii_case(file)) {
+            used.push(file.to_string());
+        }
+    };
+
+    for technique in preset.techniques() {
+        let declared = match &technique.file {
+            Some(file) => {
+                use_effect(file);
+                effects.get(&file.to_lowercase()).map(|effect| {
+                    effect
+                        .info
+                        .techniques
+                        .iter()
+                        .any(|declared| declared.name == technique.name)
+                })
+            }
+            None => Some(effects.values().any(|effect| {
+                effect
+                    .info
+                    .techniques
+                    .iter()
+                    .any(|declared| declared.name == technique.name)
+            })),
+        };
+        // a missing effect file is reported once for all its techniques
+        if declared == Some(false) {
+            issues.push(PresetIssue::MissingTechnique(technique));
+        }
+    }
+    for file in preset.effect_files() {
+        use_effect(file);
+    }
+
+    for file in used {
+        let Some(effect) = effects.get(&file.to_lowercase()) else {
+            issues.push(PresetIssue::MissingEffect(file));
+            continue;
+        };
+
+        if effect.paths.len() > 1 {
+            issues.push(PresetIssue::DuplicateEffect {
+                effect: file.clone(),
+                paths: effect.paths.clone(),
+            });
+        }
+
+        for texture in effect.info.textures.iter().filter_map(|t| t.source()) {
+            let wanted = texture.replace('\\', "/").to_lowercase();
+            let found = textures
+                .iter()
+                .any(|relative| *relative == wanted || relative.ends_with(&format!("/{wanted}")));
+            if !found {
+                issues.push(PresetIssue::MissingTexture {
+                    effect: file.clone(),
+                    texture: texture.to_string(),
+                });
+            }
+        }
+
+        if let Some(section) = preset.document().section(Some(&file)) {
+            for (uniform, _) in section.entries() {
+                if !effect.info.uniforms.iter().any(|u| u.name == uniform) {
+                    issues.push(PresetIssue::UnknownUniform {
+                        effect: file.clone(),
+                        uniform: uniform.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Finds the effect files in the search paths, keyed by their lowercase file name
+fn find_effects(
+    game_path: &Path,
+    search_paths: &[String],
+) -> ReShaderResult<BTreeMap<String, FoundEffect>> {
+    let mut effects = BTreeMap::<String, FoundEffect>::new();
+    let mut seen = HashSet::new();
+
+    for search_path in resolve_all(game_path, search_paths) {
+        let mut files = Vec::new();
+        list_files(
+            &search_path.path,
+            search_path.recursive,
+            &mut HashSet::new(),
+            &mut files,
+        )?;
+        files.sort();
+
+        for path in files {
+            let is_effect = path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("fx"));
+            if !is_effect {
+                continue;
+            }
+            // search paths often overlap or point to the same directory through symlinks
+            let Ok(canonical) = path.canonicalize() else {
+                continue;
+            };
+            if !seen.insert(canonical) {
+                continue;
+            }
+
+            let name = path.file_name().unwrap().to_string_lossy().to_lowercase();
+            match effects.get_mut(&name) {
+                Some(effect) => effect.paths.push(path),
+                None => {
+                    let source = std::fs::read(&path)?;
+                    effects.insert(
+                        name,
+                        FoundEffect {
+                            paths: vec![path],
+                            info: parse_effect(&String::from_utf8_lossy(&source)),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(effects)
+}
+
+/// Lists the files in the texture search paths as lowercase paths relative to the search path
+fn find_textures(game_path: &Path, search_paths: &[String]) -> ReShaderResult<Vec<String>> {
+    let mut textures = Vec::new();
+
+    for search_path in resolve_all(game_path, search_paths) {
+        let mut files = Vec::new();
+        list_files(
+            &search_path.path,
+            search_path.recursive,
+            &mut HashSet::new(),
+            &mut files,
+        )?;
+        textures.extend(files.iter().filter_map(|file| {
+            let relative = file.strip_prefix(&search_path.path).ok()?;
+            Some(relative.to_string_lossy().to_lowercase())
+        }));
+    }
+
+    Ok(textures)
+}
+
+fn resolve_all(game_path: &Path, search_paths: &[String]) -> Vec<SearchPath> {
+    search_paths
+        .iter()
+        .filter_map(|entry| resolve_search_path(game_path, entry))
+        .collect()
+}
+
+/// Lists the files inside a directory, following symlinks but visiting every directory only once
+fn list_files(
+    directory: &Path,
+    recursive: bool,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> ReShaderResult<()> {
+    // a symlink to a parent directory would otherwise be followed forever
+    if !directory.is_dir() || !visited.insert(directory.canonicalize()?) {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if recursive {
+                list_files(&path, recursive, visited, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn list_files_survives_symlink_loops() {
+        let tmp = TempDir::new("reshader-validate-loop").unwrap();
+        let shaders = tmp.path().join("Shaders");
+        std::fs::create_dir_all(&shaders).unwrap();
+        std::fs::write(shaders.join("Bloom.fx"), "").unwrap();
+        std::os::unix::fs::symlink("..", shaders.join("Parent")).unwrap();
+
+        let mut files = Vec::new();
+        list_files(tmp.path(), true, &mut HashSet::new(), &mut files).unwrap();
+
+        assert_eq!(files, vec![shaders.join("Bloom.fx")]);
+    }
+}
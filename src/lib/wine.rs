@@ -0,0 +1,733 @@
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{prelude::*, write_atomically};
+
+/// The DLLs ReShader links into a game directory, without their extension
+pub const RESHADE_DLLS: [&str; 2] = ["dxgi", "d3dcompiler_47"];
+
+/// The override mode ReShader sets for its DLLs
+pub const DLL_OVERRIDE_MODE: &str = "native,builtin";
+
+/// Seconds between the Windows FILETIME epoch (1601) and the Unix epoch
+const FILETIME_EPOCH_OFFSET: u64 = 11_644_473_600;
+
+/// A Wine registry file, e.g. `user.reg` inside a Wine prefix
+///
+/// Parsing and serializing a file without modifying it yields the same content,
+/// so keys ReShader doesn't know about are kept intact.
+#[derive(Debug, Clone, Default)]
+pub struct RegistryFile {
+    /// The lines before the first key, e.g. `WINE REGISTRY Version 2` and `#arch=win64`
+    pub header: Vec<String>,
+    /// The keys in the order they appear in the file
+    pub keys: Vec<RegistryKey>,
+}
+
+/// A single key (`[Software\\Wine\\DllOverrides]`) in a Wine registry file
+#[derive(Debug, Clone)]
+pub struct RegistryKey {
+    /// The unescaped path of the key, e.g. `Software\Wine\DllOverrides`
+    pub path: String,
+    /// The modification time as a Unix timestamp, written after the key name
+    pub modified: Option<u64>,
+    /// The metadata, comment, value and blank lines of this key, including the blank lines before the next key
+    pub lines: Vec<RegistryLine>,
+    /// The original header line, written instead of a new one while it still matches the path and modification time
+    header: Option<String>,
+}
+
+/// A line inside a registry key
+#[derive(Debug, Clone)]
+pub enum RegistryLine {
+    /// A named value or the default value (`@`)
+    Value(RegistryValue),
+    /// Metadata (`#time=...`), a comment or a blank line, kept verbatim
+    Other(String),
+}
+
+/// A value inside a registry key
+#[derive(Debug, Clone)]
+pub struct RegistryValue {
+    /// The unescaped name of the value, `None` for the default value (`@`)
+    pub name: Option<String>,
+    /// The data of the value
+    pub data: RegistryData,
+}
+
+/// The data of a registry value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryData {
+    /// A string value (`"..."`)
+    String(String),
+    /// A 32-bit number (`dword:...`)
+    Dword(u32),
+    /// Any other value type (`hex:...`, `str(2):...`, ...), kept verbatim
+    Raw(String),
+}
+
+impl RegistryFile {
+    /// Parses the contents of a Wine registry file
+    pub fn parse(content: &str) -> ReShaderResult<Self> {
+        let mut file = RegistryFile::default();
+        let mut lines = content.lines().enumerate();
+
+        while let Some((number, line)) = lines.next() {
+            if line.starts_with('[') {
+                file.keys.push(parse_key_header(number + 1, line)?);
+                continue;
+            }
+
+            let Some(key) = file.keys.last_mut() else {
+                file.header.push(line.to_string());
+                continue;
+            };
+
+            if !line.starts_with('"') && !line.starts_with('@') {
+                key.lines.push(RegistryLine::Other(line.to_string()));
+                continue;
+            }
+
+            // hex values can span multiple lines, each continued line ends with a backslash
+            let mut full_line = line.to_string();
+            while full_line.ends_with('\\') {
+                match lines.next() {
+                    Some((_, next)) => {
+                        full_line.push('\n');
+                        full_line.push_str(next);
+                    }
+                    None => break,
+                }
+            }
+
+            key.lines
+                .push(RegistryLine::Value(parse_value(number + 1, &full_line)?));
+        }
+
+        Ok(file)
+    }
+
+    /// Reads and parses a Wine registry file
+    pub fn load(path: &Path) -> ReShaderResult<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// Returns an empty `user.reg`, like the one Wine creates for a new prefix
+    pub fn user() -> Self {
+        Self {
+            header: vec![
+                "WINE REGISTRY Version 2".to_string(),
+                ";; All keys relative to \\\\User\\\\S-1-5-21-0-0-0-1000".to_string(),
+            ],
+            keys: Vec::new(),
+        }
+    }
+
+    /// Serializes and writes the registry file, see [`write_atomically`]
+    pub fn save(&self, path: &Path) -> ReShaderResult<()> {
+        write_atomically(path, self.to_string().as_bytes())
+    }
+
+    /// Returns the key with the given path, ignoring case like Windows does
+    pub fn key(&self, path: &str) -> Option<&RegistryKey> {
+        self.keys
+            .iter()
+            .find(|key| key.path.eq_ignore_ascii_case(path))
+    }
+
+    /// Returns the key with the given path, ignoring case like Windows does
+    pub fn key_mut(&mut self, path: &str) -> Option<&mut RegistryKey> {
+        self.keys
+            .iter_mut()
+            .find(|key| key.path.eq_ignore_ascii_case(path))
+    }
+
+    /// Returns the key with the given path, creating it if it doesn't exist
+    pub fn key_or_insert(&mut self, path: &str) -> &mut RegistryKey {
+        let index = match self
+            .keys
+            .iter()
+            .position(|key| key.path.eq_ignore_ascii_case(path))
+        {
+            Some(index) => index,
+            None => {
+                // Wine separates keys with a blank line
+                match self.keys.last_mut() {
+                    Some(key) if !key.ends_blank() => {
+                        key.lines.push(RegistryLine::Other(String::new()))
+                    }
+                    Some(_) => {}
+                    None => {
+                        if self.header.last().is_some_and(|line| !line.is_empty()) {
+                            self.header.push(String::new());
+                        }
+                    }
+                }
+                self.keys.push(RegistryKey::new(path));
+                self.keys.len() - 1
+            }
+        };
+
+        &mut self.keys[index]
+    }
+}
+
+impl Display for RegistryFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in &self.header {
+            writeln!(f, "{line}")?;
+        }
+        for key in &self.keys {
+            write!(f, "{key}")?;
+        }
+        Ok(())
+    }
+}
+
+impl RegistryKey {
+    /// Creates an empty key with the current time as its modification time
+    pub fn new(path: &str) -> Self {
+        let mut key = Self {
+            path: path.to_string(),
+            modified: None,
+            lines: Vec::new(),
+            header: None,
+        };
+        key.touch();
+        key
+    }
+
+    /// Returns the value with the given name, ignoring case like Windows does
+    pub fn value(&self, name: &str) -> Option<&RegistryData> {
+        self.lines.iter().find_map(|line| match line {
+            RegistryLine::Value(value)
+                if value
+                    .name
+                    .as_deref()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name)) =>
+            {
+                Some(&value.data)
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns all values of this key
+    pub fn values(&self) -> impl Iterator<Item = &RegistryValue> {
+        self.lines.iter().filter_map(|line| match line {
+            RegistryLine::Value(value) => Some(value),
+            RegistryLine::Other(_) => None,
+        })
+    }
+
+    /// Sets a value, replacing an existing value with the same name
+    pub fn set_value(&mut self, name: &str, data: RegistryData) {
+        let existing = self.lines.iter_mut().find_map(|line| match line {
+            RegistryLine::Value(value)
+                if value
+                    .name
+                    .as_deref()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name)) =>
+            {
+                Some(value)
+            }
+            _ => None,
+        });
+
+        match existing {
+            Some(value) if value.data == data => return,
+            Some(value) => value.data = data,
+            None => {
+                // new values go before the blank lines separating the key from the next one
+                let index = self
+                    .lines
+                    .iter()
+                    .rposition(
+                        |line| !matches!(line, RegistryLine::Other(other) if other.is_empty()),
+                    )
+                    .map_or(0, |index| index + 1);
+                self.lines.insert(
+                    index,
+                    RegistryLine::Value(RegistryValue {
+                        name: Some(name.to_string()),
+                        data,
+                    }),
+                );
+            }
+        }
+        self.touch();
+    }
+
+    /// Removes a value, returning whether it existed
+    pub fn remove_value(&mut self, name: &str) -> bool {
+        let before = self.lines.len();
+        self.lines.retain(|line| match line {
+            RegistryLine::Value(value) => !value
+                .name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name)),
+            RegistryLine::Other(_) => true,
+        });
+
+        let removed = self.lines.len() != before;
+        if removed {
+            self.touch();
+        }
+        removed
+    }
+
+    fn ends_blank(&self) -> bool {
+        matches!(self.lines.last(), Some(RegistryLine::Other(other)) if other.is_empty())
+    }
+
+    /// Updates the modification time of the key to now
+    pub fn touch(&mut self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        self.modified = Some(now);
+
+        // Wine stores the modification time a second time as a FILETIME in 100ns intervals
+        let time_line = format!("#time={:x}", (now + FILETIME_EPOCH_OFFSET) * 10_000_000);
+        let existing = self.lines.iter_mut().find_map(|line| match line {
+            RegistryLine::Other(other) if other.starts_with("#time=") => Some(other),
+            _ => None,
+        });
+        match existing {
+            Some(line) => *line = time_line,
+            None => self.lines.insert(0, RegistryLine::Other(time_line)),
+        }
+    }
+}
+
+impl Display for RegistryKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let unchanged = self.header.as_deref().filter(|header| {
+            parse_key_header(0, header)
+                .is_ok_and(|key| key.path == self.path && key.modified == self.modified)
+        });
+        match unchanged {
+            Some(header) => writeln!(f, "{header}")?,
+            None => {
+                write!(f, "[{}]", escape(&self.path))?;
+                if let Some(modified) = self.modified {
+                    write!(f, " {modified}")?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        for line in &self.lines {
+            match line {
+                RegistryLine::Value(value) => writeln!(f, "{value}")?,
+                RegistryLine::Other(other) => writeln!(f, "{other}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for RegistryValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "\"{}\"=", escape(name))?,
+            None => write!(f, "@=")?,
+        }
+        match &self.data {
+            RegistryData::String(value) => write!(f, "\"{}\"", escape(value)),
+            RegistryData::Dword(value) => write!(f, "dword:{value:08x}"),
+            RegistryData::Raw(raw) => write!(f, "{raw}"),
+        }
+    }
+}
+
+fn parse_key_header(line_number: usize, line: &str) -> ReShaderResult<RegistryKey> {
+    let (name, end) = read_until(&line[1..], ']').ok_or_else(|| {
+        ReShaderError::RegistryParse(line_number, "unterminated key name".to_string())
+    })?;
+    let modified = line[1 + end + 1..].trim().parse::<u64>().ok();
+
+    Ok(RegistryKey {
+        path: unescape(&name),
+        modified,
+        lines: Vec::new(),
+        header: Some(line.to_string()),
+    })
+}
+
+fn parse_value(line_number: usize, line: &str) -> ReShaderResult<RegistryValue> {
+    let (name, rest) = if let Some(rest) = line.strip_prefix('@') {
+        (None, rest)
+    } else {
+        let (name, end) = read_until(&line[1..], '"').ok_or_else(|| {
+            ReShaderError::RegistryParse(line_number, "unterminated value name".to_string())
+        })?;
+        (Some(unescape(&name)), &line[1 + end + 1..])
+    };
+
+    let raw = rest.strip_prefix('=').ok_or_else(|| {
+        ReShaderError::RegistryParse(line_number, "missing '=' after value name".to_string())
+    })?;
+
+    Ok(RegistryValue {
+        name,
+        data: parse_data(raw),
+    })
+}
+
+fn parse_data(raw: &str) -> RegistryData {
+    if let Some(hex) = raw.strip_prefix("dword:") {
+        if let Ok(value) = u32::from_str_radix(hex, 16) {
+            if format!("{value:08x}") == hex {
+                return RegistryData::Dword(value);
+            }
+        }
+    } else if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+        let value = unescape(&raw[1..raw.len() - 1]);
+        // only use the typed representation if it serializes back to the same text
+        if escape(&value) == raw[1..raw.len() - 1] {
+            return RegistryData::String(value);
+        }
+    }
+
+    RegistryData::Raw(raw.to_string())
+}
+
+/// Reads an escaped string until the unescaped `end` character
+///
+/// Returns the escaped content and the byte index of the `end` character.
+fn read_until(input: &str, end: char) -> Option<(String, usize)> {
+    let mut escaped = false;
+    for (index, c) in input.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == end {
+            return Some((input[..index].to_string(), index));
+        }
+    }
+    None
+}
+
+fn escape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '"' => output.push_str("\\\""),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            ' '..='~' => output.push(c),
+            _ => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    output.push_str(&format!("\\x{unit:04x}"));
+                }
+            }
+        }
+    }
+    output
+}
+
+fn unescape(input: &str) -> String {
+    let mut units: Vec<u16> = Vec::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u16; 2];
+            units.extend_from_slice(c.encode_utf16(&mut buf));
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => units.push(b'\n' as u16),
+            Some('r') => units.push(b'\r' as u16),
+            Some('t') => units.push(b'\t' as u16),
+            Some('0') => units.push(0),
+            Some('x') => {
+                let mut value = 0u16;
+                let mut digits = 0;
+                while digits < 4 {
+                    match chars.peek().and_then(|c| c.to_digit(16)) {
+                        Some(digit) => {
+                            value = value * 16 + digit as u16;
+                            chars.next();
+                            digits += 1;
+                        }
+                        None => break,
+                    }
+                }
+                units.push(value);
+            }
+            Some(other) => {
+                let mut buf = [0u16; 2];
+                units.extend_from_slice(other.encode_utf16(&mut buf));
+            }
+            None => units.push(b'\\' as u16),
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+/// Returns the registry key containing DLL overrides
+///
+/// If `executable` is provided, the key only applies to that executable (e.g. `ffxiv_dx11.exe`),
+/// otherwise it applies to the whole prefix.
+pub fn dll_overrides_key(executable: Option<&str>) -> String {
+    match executable {
+        Some(executable) => format!("Software\\Wine\\AppDefaults\\{executable}\\DllOverrides"),
+        None => "Software\\Wine\\DllOverrides".to_string(),
+    }
+}
+
+/// Returns the path to the `user.reg` file of a Wine prefix
+pub fn user_registry_path(prefix: &Path) -> PathBuf {
+    prefix.join("user.reg")
+}
+
+/// Adds DLL overrides for the given DLLs to the `user.reg` of a Wine prefix, creating it if needed
+///
+/// If `executable` is provided, the overrides are only set for that executable.
+///
+/// Wine must not be running in the prefix, otherwise it will overwrite the changes on exit.
+pub fn set_dll_overrides(
+    prefix: &Path,
+    executable: Option<&str>,
+    dlls: &[&str],
+) -> ReShaderResult<()> {
+    let registry_path = user_registry_path(prefix);
+    // Wine only creates user.reg when the prefix is first started
+    let mut registry = if registry_path.exists() {
+        RegistryFile::load(&registry_path)?
+    } else {
+        RegistryFile::user()
+    };
+
+    let key = registry.key_or_insert(&dll_overrides_key(executable));
+    for dll in dlls {
+        key.set_value(dll, RegistryData::String(DLL_OVERRIDE_MODE.to_string()));
+    }
+
+    registry.save(&registry_path)
+}
+
+/// Removes the DLL overrides for the given DLLs from the `user.reg` of a Wine prefix
+///
+/// If `executable` is provided, only the overrides for that executable are removed.
+///
+/// Wine must not be running in the prefix, otherwise it will overwrite the changes on exit.
+pub fn remove_dll_overrides(
+    prefix: &Path,
+    executable: Option<&str>,
+    dlls: &[&str],
+) -> ReShaderResult<()> {
+    let registry_path = user_registry_path(prefix);
+    let mut registry = RegistryFile::load(&registry_path)?;
+
+    let Some(key) = registry.key_mut(&dll_overrides_key(executable)) else {
+        return Ok(());
+    };
+
+    let mut changed = false;
+    for dll in dlls {
+        changed |= key.remove_value(dll);
+    }
+
+    if changed {
+        registry.save(&registry_path)?;
+    }
+    Ok(())
+}
+
+/// Merges DLL overrides for the given DLLs into a `WINEDLLOVERRIDES` value
+///
+/// Entries for other DLLs are kept as they are, existing entries for the given DLLs are replaced.
+pub fn merge_dll_overrides(existing: Option<&str>, dlls: &[&str]) -> String {
+    let mut entries = existing
+        .unwrap_or_default()
+        .split(';')
+        .filter_map(|entry| {
+            let entry = entry.trim();
+            let (names, mode) = match entry.split_once('=') {
+                Some((names, mode)) => (names, Some(mode)),
+                None => (entry, None),
+            };
+            let names = names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| {
+                    !name.is_empty() && !dlls.iter().any(|dll| dll.eq_ignore_ascii_case(name))
+                })
+                .collect::<Vec<_>>();
+            if names.is_empty() {
+                return None;
+            }
+
+            Some(match mode {
+                Some(mode) => format!("{}={mode}", names.join(",")),
+                None => names.join(","),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if !dlls.is_empty() {
+        entries.push(format!("{}={DLL_OVERRIDE_MODE}", dlls.join(",")));
+    }
+    entries.join(";")
+}
+
+/// Guesses the Wine prefix a game runs in from its location
+///
+/// Games installed inside a prefix (`<prefix>/drive_c/...`) use that prefix. Steam games
+/// (`steamapps/common/<game>`) use the Proton prefix of the app that is installed to that directory.
+pub fn find_wine_prefix(game_path: &Path) -> Option<PathBuf> {
+    if let Some(prefix) = game_path
+        .ancestors()
+        .find(|path| path.join("drive_c").is_dir() && user_registry_path(path).is_file())
+    {
+        return Some(prefix.to_path_buf());
+    }
+
+    let common = game_path
+        .ancestors()
+        .find(|path| path.ends_with("steamapps/common"))?;
+    let install_dir = game_path.strip_prefix(common).ok()?.iter().next()?;
+    let steamapps = common.parent()?;
+
+    std::fs::read_dir(steamapps)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("appmanifest_") && name.ends_with(".acf")
+        })
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .find_map(|manifest| {
+            let value = |key: &str| {
+                manifest.lines().find_map(|line| {
+                    let mut parts = line.split('"').filter(|part| !part.trim().is_empty());
+                    if parts.next()? != key {
+                        return None;
+                    }
+                    parts.next().map(str::to_string)
+                })
+            };
+            if value("installdir")? != install_dir.to_str()? {
+                return None;
+            }
+            let prefix = steamapps
+                .join("compatdata")
+                .join(value("appid")?)
+                .join("pfx");
+            user_registry_path(&prefix).is_file().then_some(prefix)
+        })
+}
+
+/// Returns the given DLLs that don't have a native override in the `user.reg` of a Wine prefix
+///
+/// Overrides for the whole prefix and for any of the given executables count.
+pub fn missing_dll_overrides(
+    prefix: &Path,
+    executables: &[&str],
+    dlls: &[&str],
+) -> ReShaderResult<Vec<String>> {
+    let registry = RegistryFile::load(&user_registry_path(prefix))?;
+    let keys = std::iter::once(dll_overrides_key(None))
+        .chain(
+            executables
+                .iter()
+                .map(|executable| dll_overrides_key(Some(executable))),
+        )
+        .filter_map(|path| registry.key(&path))
+        .collect::<Vec<_>>();
+
+    Ok(dlls
+        .iter()
+        .filter(|dll| {
+            !keys.iter().any(|key| {
+                matches!(key.value(dll), Some(RegistryData::String(mode)) if mode.trim_start().starts_with('n'))
+            })
+        })
+        .map(|dll| dll.to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An excerpt of the `user.reg` of a Proton prefix
+    const USER_REG: &str = r#"WINE REGISTRY Version 2
+;; All keys relative to \\User\\S-1-5-21-0-0-0-1000
+
+#arch=win64
+
+[Console] 1677000000
+#time=1d945b2c3f8e0a0
+"ColorTable00"=dword:00000000
+"CursorSize"=dword:00000019
+"FaceName"=""
+
+[Control Panel\\Desktop] 1677000000
+#time=1d945b2c3f8e0a0
+"DragFullWindows"="0"
+"UserPreferencesMask"=hex:10,00,02,80,10,00,00,00,00,00,00,00,00,00,00,00,00,00,\
+  00,00
+
+[Software\\Wine\\DllOverrides] 1677000123
+#time=1d945b2c4a1b2c0
+"*d3d11"="native"
+"dxgi"="native,builtin"
+
+[Software\\Wine\\Fonts\\Replacements] 1677000000
+#time=1d945b2c3f8e0a0
+@="MS Shell Dlg"
+"#;
+
+    #[test]
+    fn user_reg_round_trips() {
+        assert_eq!(RegistryFile::parse(USER_REG).unwrap().to_string(), USER_REG);
+
+        // blank lines added by hand stay where they are
+        let edited = USER_REG
+            .replace("\"CursorSize\"", "\n\"CursorSize\"")
+            .replace(
+                "[Software\\\\Wine\\\\DllOverrides]",
+                "\n[Software\\\\Wine\\\\DllOverrides]",
+            );
+        assert_eq!(RegistryFile::parse(&edited).unwrap().to_string(), edited);
+    }
+
+    #[test]
+    fn changes_only_touch_the_changed_key() {
+        let mut registry = RegistryFile::parse(USER_REG).unwrap();
+        let key = registry.key_mut(&dll_overrides_key(None)).unwrap();
+        key.set_value(
+            "d3dcompiler_47",
+            RegistryData::String(DLL_OVERRIDE_MODE.to_string()),
+        );
+        let modified = key.modified.unwrap();
+        let time_line = key.lines[0].clone();
+        registry.key_or_insert(&dll_overrides_key(Some("ffxiv_dx11.exe")));
+
+        let RegistryLine::Other(time_line) = time_line else {
+            panic!("the first line of a key is its time");
+        };
+        let expected = USER_REG
+            .replace(
+                "[Software\\\\Wine\\\\DllOverrides] 1677000123\n#time=1d945b2c4a1b2c0\n\"*d3d11\"=\"native\"\n\"dxgi\"=\"native,builtin\"\n",
+                &format!("[Software\\\\Wine\\\\DllOverrides] {modified}\n{time_line}\n\"*d3d11\"=\"native\"\n\"dxgi\"=\"native,builtin\"\n\"d3dcompiler_47\"=\"native,builtin\"\n"),
+            );
+        let written = registry.to_string();
+        assert!(written.starts_with(&expected), "{written}");
+        assert!(written[expected.len()..]
+            .starts_with("\n[Software\\\\Wine\\\\AppDefaults\\\\ffxiv_dx11.exe\\\\DllOverrides]"));
+    }
+}
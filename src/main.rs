@@ -1,6 +1,7 @@
 use std::{
     fmt::{Display, Formatter},
-    path::PathBuf,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
     process::exit,
 };
 
@@ -9,27 +10,41 @@ use cli::SubCommand;
 use inquire::error::InquireResult;
 use strum::{EnumIter, IntoEnumIterator};
 
-use crate::config::Config;
 use reshaderlib::{
-    download_minimal_reshade_shaders, download_reshade, download_shader_collections,
-    install_preset_for_game, install_presets, install_reshade, install_reshade_shaders, uninstall,
+    catalog::{ShaderCatalog, CATALOG_FILE},
+    collection_index::{build_collection_index, resolve_preset, CollectionIndex, INDEX_FILE},
+    config::Config,
+    doctor::{diagnose, fix_problem},
+    find_foreign_dlls,
+    gshade::{
+        convert_presets, find_gshade_downloads, DownloadWatcher, GShadeDownloads, GShadeMapping,
+        GShadeSource,
+    },
+    launch::{prepare_command, resolve_game_path},
+    manager::ReShaderManager,
+    minimal_collection_names,
+    plan::Plan,
+    prelude::{ReShaderError, ReShaderResult},
+    preset::Preset,
+    preset_store::{apply_preset, PresetStore},
+    reshade_ini::{backup, known_key, validate_list_key, validate_value, ReShadeIni},
+    status::game_status,
+    update::GameUpdate,
+    validate::validate_preset,
+    wine::{find_wine_prefix, remove_dll_overrides, set_dll_overrides, RESHADE_DLLS},
     SHADER_COLLECTIONS,
 };
 
 mod cli;
-mod config;
 mod tui;
 
-static QUALIFIER: &str = "eu";
-static ORGANIZATION: &str = "cozysoft";
-static APPLICATION: &str = "reshader";
-
 #[derive(Debug, EnumIter)]
 enum InstallOption {
     ReShade,
     ReShadeVanilla,
     ReShadeShaders,
     GShadePresets,
+    Update,
     Uninstall,
     Quit,
 }
@@ -47,6 +62,7 @@ impl Display for InstallOption {
                 f,
                 "Install/Update GShade shaders and presets (install ReShade first)"
             ),
+            InstallOption::Update => write!(f, "Update ReShade, shaders and presets of all games"),
             InstallOption::Uninstall => write!(f, "Uninstall ReShade/GShade"),
             InstallOption::Quit => write!(f, "Quit"),
         }
@@ -69,60 +85,53 @@ impl Display for ReShadeShadersOptions {
 }
 
 async fn tui(
-    config: &mut Config,
+    manager: &mut ReShaderManager,
     client: &reqwest::Client,
-    data_dir: &PathBuf,
-    config_path: &PathBuf,
     specific_installer: Option<String>,
 ) -> InquireResult<()> {
+    let data_dir = &manager.data_dir().to_path_buf();
     loop {
         let install_option =
             inquire::Select::new("Select an option", InstallOption::iter().collect()).prompt()?;
 
         let result = match install_option {
             InstallOption::ReShade => {
-                download_reshade(client, data_dir, false, None, &specific_installer).await?;
+                manager
+                    .download_reshade(client, false, None, &specific_installer)
+                    .await?;
                 let install_now = tui::prompt_install()?;
                 if install_now {
                     let game_path = tui::prompt_game_path()?;
-                    install_reshade(data_dir, &game_path, false).await?;
+                    let game_path = tui::prompt_install_directory(&game_path)?;
+                    let link_mode = tui::prompt_link_mode(manager.config().link_mode(&game_path))?;
+                    let chain_load = prompt_foreign_dlls(data_dir, &game_path)?;
+                    manager.install_reshade(
+                        &game_path,
+                        false,
+                        chain_load,
+                        Some(link_mode),
+                        None,
+                    )?;
                     tui::print_reshade_success();
 
-                    if config
-                        .game_paths
-                        .contains(&game_path.to_str().unwrap().to_string())
-                    {
-                        return Ok(());
-                    }
-
-                    config
-                        .game_paths
-                        .push(game_path.to_str().unwrap().to_string());
-
                     Ok(())
                 } else {
                     Ok(())
                 }
             }
             InstallOption::ReShadeVanilla => {
-                download_reshade(client, data_dir, true, None, &specific_installer).await?;
+                manager
+                    .download_reshade(client, true, None, &specific_installer)
+                    .await?;
                 let install_now = tui::prompt_install()?;
                 if install_now {
                     let game_path = tui::prompt_game_path()?;
-                    install_reshade(data_dir, &game_path, true).await?;
+                    let game_path = tui::prompt_install_directory(&game_path)?;
+                    let link_mode = tui::prompt_link_mode(manager.config().link_mode(&game_path))?;
+                    let chain_load = prompt_foreign_dlls(data_dir, &game_path)?;
+                    manager.install_reshade(&game_path, true, chain_load, Some(link_mode), None)?;
                     tui::print_reshade_success();
 
-                    if config
-                        .game_paths
-                        .contains(&game_path.to_str().unwrap().to_string())
-                    {
-                        return Ok(());
-                    }
-
-                    config
-                        .game_paths
-                        .push(game_path.to_str().unwrap().to_string());
-
                     Ok(())
                 } else {
                     Ok(())
@@ -135,42 +144,42 @@ async fn tui(
                 )
                 .prompt()?;
 
-                match shader_install_option {
+                let collections = match shader_install_option {
                     ReShadeShadersOptions::Minimal => {
                         tui::print_downloading_shaders();
-                        download_minimal_reshade_shaders(data_dir).await?;
+                        manager.download_minimal_shaders().await?;
+                        minimal_collection_names()
                     }
                     ReShadeShadersOptions::Select => {
                         let collections =
                             tui::prompt_select_select_shaders(SHADER_COLLECTIONS.iter().collect())?;
                         tui::print_downloading_shaders();
-                        download_shader_collections(&collections, data_dir).await?;
+                        manager.download_shaders(&collections).await?;
+                        collections
+                            .iter()
+                            .map(|collection| collection.name.clone())
+                            .collect()
                     }
-                }
+                };
 
                 let install_now = tui::prompt_install_shaders()?;
 
                 if install_now {
-                    if config.game_paths.is_empty() {
+                    if manager.games().is_empty() {
                         tui::print_no_game_paths();
                         return Ok(());
                     }
-                    let game_paths =
-                        tui::prompt_select_game_paths_shaders(config.game_paths.clone())?;
+                    let game_paths = tui::prompt_select_game_paths_shaders(&manager.game_paths())?;
                     for game_path in &game_paths {
-                        let game_path = PathBuf::from(game_path);
-
                         if game_path.join("gshade-shaders").exists() {
                             let replace = tui::prompt_reshade_gshade_replacement()?;
-                            if replace {
-                                std::fs::remove_dir_all(game_path.join("gshade-shaders"))?;
-                                install_reshade_shaders(&data_dir.join("Merged"), &game_path)?;
-                            } else {
+                            if !replace {
                                 continue;
                             }
-                        } else {
-                            install_reshade_shaders(&data_dir.join("Merged"), &game_path)?;
+                            let kept = manager.remove_link(game_path, "gshade-shaders")?;
+                            tui::print_uninstall_kept(&kept);
                         }
+                        manager.install_reshade_shaders(game_path, None, &collections)?;
                     }
                     tui::print_shader_install_successful();
                     Ok(())
@@ -185,26 +194,48 @@ async fn tui(
                 let open = tui::prompt_open_links()?;
 
                 if open {
-                    tui::print_gshade_file_move(data_dir);
+                    tui::print_gshade_file_location();
 
                     let _ = open::that("https://gitlab.com/Mortalitas/GShade-C-Shaders");
                     let _ = open::that("https://gitlab.com/Mortalitas/GShade-Presets");
                     tui::print_gshade_hint();
                 }
 
+                let download_dir = directories::UserDirs::new()
+                    .and_then(|dirs| dirs.download_dir().map(PathBuf::from));
+                let watcher = download_dir.as_deref().map(DownloadWatcher::start);
+
                 let done = tui::prompt_confirm_move()?;
+                let recent = watcher.map(DownloadWatcher::stop).unwrap_or_default();
 
                 if !done {
                     continue;
                 }
-                install_presets(
-                    data_dir,
-                    &data_dir.join("GShade-Presets-master.zip"),
-                    &data_dir.join("GShade-C-Shaders-main.zip"),
-                )
-                .await?;
+                let downloads = match &download_dir {
+                    // a missing or unreadable downloads folder only means the paths have to be entered
+                    Some(download_dir) => {
+                        find_gshade_downloads(download_dir, &recent).unwrap_or_default()
+                    }
+                    None => GShadeDownloads::default(),
+                };
+                let presets_path = match downloads.presets {
+                    Some(path) if tui::prompt_use_gshade_download("presets", &path)? => path,
+                    _ => tui::prompt_gshade_presets_path(data_dir)?,
+                };
+                let subfolders = GShadeSource::open(&presets_path)?.preset_subfolders()?;
+                let presets_subfolder = match subfolders.len() {
+                    0 | 1 => None,
+                    _ => tui::prompt_select_presets_subfolder(&subfolders)?,
+                };
+                let shaders_path = match downloads.shaders {
+                    Some(path) if tui::prompt_use_gshade_download("shaders", &path)? => path,
+                    _ => tui::prompt_gshade_shaders_path(data_dir)?,
+                };
+                manager
+                    .download_presets(&presets_path, presets_subfolder.as_deref(), &shaders_path)
+                    .await?;
 
-                if config.game_paths.is_empty() {
+                if manager.games().is_empty() {
                     tui::print_presets_success_no_games(data_dir);
                     continue;
                 }
@@ -216,39 +247,52 @@ async fn tui(
                     continue;
                 }
 
-                let game_paths = tui::prompt_select_game_paths(config.game_paths.clone())?;
+                let game_paths = tui::prompt_select_game_paths(&manager.game_paths())?;
                 for game_path in &game_paths {
-                    let game_path = PathBuf::from(game_path);
-
                     if game_path.join("reshade-shaders").exists() {
                         let replace = tui::prompt_gshade_reshade_replacement()?;
-                        if replace {
-                            std::fs::remove_dir_all(game_path.join("reshade-shaders"))?;
-                            install_preset_for_game(data_dir, &game_path)?;
-                        } else {
+                        if !replace {
                             continue;
                         }
-                    } else {
-                        install_preset_for_game(data_dir, &game_path)?;
+                        let kept = manager.remove_link(game_path, "reshade-shaders")?;
+                        tui::print_uninstall_kept(&kept);
                     }
+                    manager.install_presets_for_game(game_path, None)?;
                 }
 
                 tui::print_presets_success();
 
                 Ok(())
             }
+            InstallOption::Update => {
+                if manager.games().is_empty() {
+                    tui::print_no_game_paths();
+                    continue;
+                }
+
+                tui::print_updating();
+                let game_paths = manager.game_paths();
+                let updates = manager
+                    .update(client, &game_paths, &specific_installer)
+                    .await?;
+                tui::print_updates(&updates);
+
+                Ok(())
+            }
             InstallOption::Uninstall => {
-                if config.game_paths.is_empty() {
+                if manager.games().is_empty() {
                     tui::print_no_game_paths();
                     return Ok(());
                 }
 
-                let game_path = tui::prompt_select_game_path_uninstall(config.game_paths.clone())?;
-                uninstall(&game_path)?;
-
-                config
-                    .game_paths
-                    .retain(|path| path != &game_path.to_str().unwrap().to_string());
+                let game_path = tui::prompt_select_game_path_uninstall(&manager.game_paths())?;
+                let plan = manager.plan_uninstall(&game_path)?;
+                tui::print_plans(std::slice::from_ref(&plan));
+                if !tui::prompt_apply_plan()? {
+                    continue;
+                }
+                manager.apply_uninstall(&plan)?;
+                tui::print_uninstall_kept(&plan.kept);
 
                 Ok(())
             }
@@ -259,97 +303,694 @@ async fn tui(
             continue;
         }
 
-        let config_str =
-            toml::to_string(&config).expect("if you see this error, the toml library is broken");
-        std::fs::write(config_path, config_str)?;
+        manager.save()?;
+    }
+
+    Ok(())
+}
+
+fn game_directory(manager: &ReShaderManager, game: &str) -> ReShaderResult<PathBuf> {
+    Ok(manager.config().resolve_game(Path::new(game))?.0)
+}
+
+fn games_in_prefix(game_paths: &[PathBuf], prefix: &Path) -> Vec<PathBuf> {
+    let prefix = prefix
+        .canonicalize()
+        .unwrap_or_else(|_| prefix.to_path_buf());
+    game_paths
+        .iter()
+        .filter(|game_path| {
+            find_wine_prefix(game_path)
+                .map(|game_prefix| game_prefix.canonicalize().unwrap_or(game_prefix))
+                .is_some_and(|game_prefix| game_prefix == prefix)
+        })
+        .cloned()
+        .collect()
+}
+
+fn ini_game_paths(target: &cli::IniTarget, config: &Config) -> ReShaderResult<Vec<PathBuf>> {
+    if target.all {
+        Ok(config.game_paths())
+    } else {
+        target
+            .game
+            .iter()
+            .map(|game| Ok(config.resolve_game(Path::new(game))?.0))
+            .collect()
+    }
+}
+
+fn edit_ini(
+    game_paths: &[PathBuf],
+    mut edit: impl FnMut(&mut ReShadeIni) -> bool,
+) -> InquireResult<()> {
+    for game_path in game_paths {
+        let ini_path = game_path.join("ReShade.ini");
+        if !ini_path.exists() {
+            tui::print_no_reshade_ini(game_path);
+            continue;
+        }
+
+        let mut reshade_ini = ReShadeIni::load(&ini_path)?;
+        if edit(&mut reshade_ini) {
+            backup(&ini_path)?;
+            reshade_ini.save(&ini_path)?;
+            tui::print_ini_changed(game_path);
+        } else {
+            tui::print_ini_unchanged(game_path);
+        }
+    }
+
+    Ok(())
+}
+
+fn ini(action: cli::IniAction, manager: &ReShaderManager) -> InquireResult<()> {
+    let config = manager.config();
+    match action {
+        cli::IniAction::Get {
+            section,
+            key,
+            target,
+        } => {
+            for game_path in ini_game_paths(&target, config)? {
+                let ini_path = game_path.join("ReShade.ini");
+                if !ini_path.exists() {
+                    tui::print_no_reshade_ini(&game_path);
+                    continue;
+                }
+
+                let reshade_ini = ReShadeIni::load(&ini_path)?;
+                let key = known_key(section, &key).map_or(key.as_str(), |known| known.key);
+                tui::print_ini_value(&game_path, reshade_ini.get_string(section, key));
+            }
+        }
+        cli::IniAction::Set {
+            section,
+            key,
+            value,
+            allow_unknown,
+            target,
+        } => {
+            let key = match validate_value(section, &key, &value) {
+                Ok(known) => known.key.to_string(),
+                Err(ReShaderError::UnknownIniKey(..)) if allow_unknown => key,
+                Err(e) => return Err(e.into()),
+            };
+
+            let _lock = manager.lock()?;
+            edit_ini(&ini_game_paths(&target, config)?, |reshade_ini| {
+                reshade_ini.set_string(section, &key, &value)
+            })?;
+        }
+        cli::IniAction::Add {
+            section,
+            key,
+            value,
+            target,
+        } => {
+            let known = validate_list_key(section, &key)?;
+
+            let _lock = manager.lock()?;
+            edit_ini(&ini_game_paths(&target, config)?, |reshade_ini| {
+                match (known.key, value.split_once('=')) {
+                    ("PreprocessorDefinitions", Some((name, definition))) => {
+                        reshade_ini.set_preprocessor_definition(name.trim(), definition.trim())
+                    }
+                    _ => reshade_ini.add_to_list(section, known.key, &value),
+                }
+            })?;
+        }
+        cli::IniAction::Remove {
+            section,
+            key,
+            value,
+            target,
+        } => {
+            let known = validate_list_key(section, &key)?;
+
+            let _lock = manager.lock()?;
+            edit_ini(&ini_game_paths(&target, config)?, |reshade_ini| {
+                if known.key == "PreprocessorDefinitions" {
+                    let name = value
+                        .split_once('=')
+                        .map_or(value.as_str(), |(name, _)| name);
+                    reshade_ini.remove_preprocessor_definition(name.trim())
+                } else {
+                    reshade_ini.remove_from_list(section, known.key, &value)
+                }
+            })?;
+        }
     }
 
     Ok(())
 }
 
+fn presets(action: cli::PresetsAction, manager: &ReShaderManager) -> InquireResult<()> {
+    let store = PresetStore::open(manager.data_dir())?;
+
+    match action {
+        cli::PresetsAction::Import { source } => {
+            let _lock = manager.lock()?;
+            let imported = store.import(&PathBuf::from(source))?;
+            tui::print_presets_imported(&imported);
+        }
+        cli::PresetsAction::List => tui::print_stored_presets(&store.list()?),
+        cli::PresetsAction::Search { term } => tui::print_stored_presets(&store.search(&term)?),
+        cli::PresetsAction::Export { name, destination } => {
+            let preset = store.find(&name)?;
+            let destination = store.export(&preset, &PathBuf::from(destination))?;
+            tui::print_preset_exported(&destination);
+        }
+        cli::PresetsAction::Apply { name, target } => {
+            let preset = store.find(&name)?;
+            let _lock = manager.lock()?;
+            for game_path in ini_game_paths(&target, manager.config())? {
+                if apply_preset(&preset.path, &game_path)? {
+                    tui::print_ini_changed(&game_path);
+                } else {
+                    tui::print_ini_unchanged(&game_path);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn scan_catalog(manager: &ReShaderManager) -> InquireResult<ShaderCatalog> {
+    let data_dir = manager.data_dir();
+    let _lock = manager.lock()?;
+    let index_path = data_dir.join(INDEX_FILE);
+    let index = if index_path.exists() {
+        Some(CollectionIndex::load(&index_path)?)
+    } else {
+        None
+    };
+
+    let catalog = ShaderCatalog::scan(data_dir, &SHADER_COLLECTIONS, index.as_ref())?;
+    catalog.save(&data_dir.join(CATALOG_FILE))?;
+    Ok(catalog)
+}
+
+fn shaders(action: cli::ShadersAction, manager: &ReShaderManager) -> InquireResult<()> {
+    match action {
+        cli::ShadersAction::Scan => {
+            let catalog = scan_catalog(manager)?;
+            tui::print_catalog_scanned(&catalog);
+        }
+        cli::ShadersAction::Search { term, rescan } => {
+            let catalog_path = manager.data_dir().join(CATALOG_FILE);
+            let catalog = if rescan || !catalog_path.exists() {
+                scan_catalog(manager)?
+            } else {
+                ShaderCatalog::load(&catalog_path)?
+            };
+
+            tui::print_catalog_matches(&catalog.search(&term));
+        }
+    }
+
+    Ok(())
+}
+
+fn prompt_foreign_dlls(data_dir: &Path, game_path: &Path) -> InquireResult<bool> {
+    let foreign = find_foreign_dlls(data_dir, game_path)?;
+    if foreign.is_empty() {
+        return Ok(false);
+    }
+
+    tui::print_foreign_dlls(&foreign);
+    if foreign.iter().any(|path| path.ends_with("dxgi.dll")) {
+        tui::prompt_chain_load()
+    } else {
+        Ok(false)
+    }
+}
+
+fn show_plans(plans: &[Plan], options: &cli::PlanOptions) {
+    if options.json {
+        let json = serde_json::to_string_pretty(plans).expect("plans can always be serialized");
+        println!("{json}");
+    } else {
+        tui::print_plans(plans);
+    }
+}
+
 async fn cli(
     subcommand: SubCommand,
-    config: &mut Config,
+    manager: &mut ReShaderManager,
     client: &reqwest::Client,
-    data_dir: &PathBuf,
-    config_path: &PathBuf,
     specific_installer: Option<String>,
 ) -> InquireResult<()> {
+    let data_dir = &manager.data_dir().to_path_buf();
     match subcommand {
         cli::SubCommand::InstallReshade {
             vanilla,
             version,
             game,
+            executable,
+            wine_prefix,
+            wine_executable,
+            chain_load,
+            link_mode,
+            pin,
+            plan,
         } => {
-            download_reshade(client, data_dir, vanilla, version, &specific_installer).await?;
-            if let Some(game) = game {
-                let game_path = PathBuf::from(game);
-                install_reshade(data_dir, &game_path, vanilla).await?;
-                tui::print_reshade_success();
-
-                if config
-                    .game_paths
-                    .contains(&game_path.to_str().unwrap().to_string())
-                {
+            let pinned_version = version.clone().filter(|_| pin);
+            match &pinned_version {
+                Some(version) => {
+                    manager
+                        .download_pinned_reshade(client, vanilla, version, &specific_installer)
+                        .await?;
+                }
+                None => {
+                    manager
+                        .download_reshade(client, vanilla, version, &specific_installer)
+                        .await?;
+                }
+            }
+            let (game_path, executable) = match (executable, game) {
+                (Some(executable), _) => {
+                    let executable = PathBuf::from(executable);
+                    (executable.parent().map(PathBuf::from), Some(executable))
+                }
+                (None, Some(game)) => {
+                    let (game_path, executable) =
+                        manager.config().resolve_game(&PathBuf::from(game))?;
+                    if let Some(executable) = &executable {
+                        if !plan.json {
+                            tui::print_executable_detected(executable);
+                        }
+                    }
+                    (
+                        Some(game_path),
+                        executable.map(|executable| executable.path),
+                    )
+                }
+                (None, None) => (None, None),
+            };
+            let executable_name = executable
+                .as_ref()
+                .and_then(|executable| executable.file_name())
+                .map(|name| name.to_string_lossy().to_string());
+            if let Some(game_path) = game_path {
+                if plan.dry_run {
+                    show_plans(
+                        &[manager.plan_install_reshade(
+                            &game_path,
+                            vanilla,
+                            chain_load,
+                            link_mode,
+                            pinned_version.as_deref(),
+                        )?],
+                        &plan,
+                    );
                     return Ok(());
                 }
+                tui::print_foreign_dlls(&find_foreign_dlls(data_dir, &game_path)?);
+                let game = manager.install_reshade(
+                    &game_path,
+                    vanilla,
+                    chain_load,
+                    link_mode,
+                    pinned_version.as_deref(),
+                )?;
+                if let Some(executable) = &executable_name {
+                    game.executable = Some(executable.clone());
+                }
+                tui::print_reshade_success();
 
-                config
-                    .game_paths
-                    .push(game_path.to_str().unwrap().to_string());
+                if let Some(wine_prefix) = wine_prefix {
+                    let _lock = manager.lock()?;
+                    set_dll_overrides(
+                        &PathBuf::from(&wine_prefix),
+                        wine_executable.as_deref(),
+                        &RESHADE_DLLS,
+                    )?;
+                    tui::print_dll_overrides_set(&wine_prefix);
+                }
             } else {
                 tui::print_reshade_success_no_games(data_dir);
             }
         }
-        cli::SubCommand::InstallReshadeShaders { game } => {
-            tui::print_downloading_shaders();
-            download_minimal_reshade_shaders(data_dir).await?;
+        cli::SubCommand::InstallReshadeShaders {
+            game,
+            link_mode,
+            plan,
+        } => {
+            if !plan.json {
+                tui::print_downloading_shaders();
+            }
+            manager.download_minimal_shaders().await?;
 
-            if let Some(game_path) = game {
-                let game_path = PathBuf::from(game_path);
-                install_reshade_shaders(data_dir, &game_path)?;
+            if let Some(game) = game {
+                let game_path = game_directory(manager, &game)?;
+                if plan.dry_run {
+                    show_plans(
+                        &[manager.plan_install_reshade_shaders(&game_path, link_mode)?],
+                        &plan,
+                    );
+                    return Ok(());
+                }
+                manager.install_reshade_shaders(
+                    &game_path,
+                    link_mode,
+                    &minimal_collection_names(),
+                )?;
                 tui::print_shader_install_successful();
             } else {
                 tui::print_shader_download_successful();
             }
         }
+        cli::SubCommand::InstallPresetShaders {
+            preset,
+            game,
+            refresh_index,
+            link_mode,
+            plan,
+        } => {
+            let index_path = data_dir.join(INDEX_FILE);
+            let index = if refresh_index || !index_path.exists() {
+                if !plan.json {
+                    tui::print_indexing_collections();
+                }
+                let index =
+                    build_collection_index(&SHADER_COLLECTIONS.iter().collect::<Vec<_>>()).await?;
+                let _lock = manager.lock()?;
+                index.save(&index_path)?;
+                index
+            } else {
+                CollectionIndex::load(&index_path)?
+            };
+
+            let preset = Preset::load(&PathBuf::from(preset))?;
+            let resolution = resolve_preset(&preset, &index, &SHADER_COLLECTIONS);
+            if !plan.json {
+                tui::print_preset_resolution(&resolution);
+                tui::print_downloading_shaders();
+            }
+            manager.download_shaders(&resolution.collections).await?;
+
+            if let Some(game) = game {
+                let game_path = game_directory(manager, &game)?;
+                if plan.dry_run {
+                    show_plans(
+                        &[manager.plan_install_reshade_shaders(&game_path, link_mode)?],
+                        &plan,
+                    );
+                    return Ok(());
+                }
+                let names = resolution
+                    .collections
+                    .iter()
+                    .map(|collection| collection.name.as_str())
+                    .collect::<Vec<_>>();
+                manager.install_reshade_shaders(&game_path, link_mode, &names)?;
+                tui::print_shader_install_successful();
+            } else {
+                tui::print_shader_download_successful();
+            }
+        }
+        cli::SubCommand::ValidatePreset { preset, game } => {
+            let issues = validate_preset(
+                &Preset::load(&PathBuf::from(preset))?,
+                &game_directory(manager, &game)?,
+            )?;
+            tui::print_preset_issues(&issues);
+            if !issues.is_empty() {
+                exit(1);
+            }
+        }
+        cli::SubCommand::ConvertPresets {
+            paths,
+            output,
+            mapping,
+        } => {
+            let mapping = match mapping {
+                Some(mapping) => GShadeMapping::load(&PathBuf::from(mapping))?,
+                None => GShadeMapping::builtin(),
+            };
+            let paths = if paths.is_empty() {
+                vec![data_dir.join("reshade-presets")]
+            } else {
+                paths.into_iter().map(PathBuf::from).collect()
+            };
+            let index_path = data_dir.join(INDEX_FILE);
+            let index = if index_path.exists() {
+                Some(CollectionIndex::load(&index_path)?)
+            } else {
+                None
+            };
+
+            let catalog_path = data_dir.join(CATALOG_FILE);
+            let catalog = if catalog_path.exists() {
+                Some(ShaderCatalog::load(&catalog_path)?)
+            } else {
+                None
+            };
+
+            let output = output.map(PathBuf::from);
+            let _lock = manager.lock()?;
+            let reports = convert_presets(
+                &paths,
+                output.as_deref(),
+                &mapping,
+                index.as_ref(),
+                catalog.as_ref(),
+            )?;
+            tui::print_conversion_reports(&reports, index.is_some(), catalog.is_some());
+        }
         cli::SubCommand::InstallPresets {
             all,
             game,
             presets,
+            presets_subfolder,
+            all_presets,
             shaders,
+            link_mode,
+            plan,
         } => {
             let presets_path = PathBuf::from(presets);
             let shaders_path = PathBuf::from(shaders);
+            let presets_subfolder = match (presets_subfolder, all_presets) {
+                (Some(subfolder), _) => Some(subfolder),
+                (None, true) => None,
+                (None, false) => GShadeSource::open(&presets_path)?.default_presets_subfolder()?,
+            };
 
-            install_presets(data_dir, &presets_path, &shaders_path).await?;
-            if all {
-                for game_path in &config.game_paths {
-                    let game_path = PathBuf::from(game_path);
-                    install_preset_for_game(data_dir, &game_path)?;
+            manager
+                .download_presets(&presets_path, presets_subfolder.as_deref(), &shaders_path)
+                .await?;
+            let game_paths = if all {
+                manager.game_paths()
+            } else {
+                game.map(|game| game_directory(manager, &game))
+                    .transpose()?
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            };
+            if !game_paths.is_empty() {
+                if plan.dry_run {
+                    let install_plans = game_paths
+                        .iter()
+                        .map(|game_path| {
+                            manager.plan_install_presets_for_game(game_path, link_mode)
+                        })
+                        .collect::<ReShaderResult<Vec<_>>>()?;
+                    show_plans(&install_plans, &plan);
+                    return Ok(());
+                }
+                for game_path in &game_paths {
+                    manager.install_presets_for_game(game_path, link_mode)?;
                 }
 
                 tui::print_presets_success();
-            } else if let Some(game) = game {
-                let game_path = PathBuf::from(game);
-                install_preset_for_game(data_dir, &game_path)?;
+            }
+        }
+        cli::SubCommand::Uninstall {
+            game,
+            wine_prefix,
+            wine_executable,
+            plan,
+        } => {
+            let game_path = game_directory(manager, &game)?;
+            let uninstall_plan = manager.plan_uninstall(&game_path)?;
+            if plan.dry_run {
+                show_plans(&[uninstall_plan], &plan);
+                return Ok(());
+            }
+            manager.apply_uninstall(&uninstall_plan)?;
+            tui::print_uninstall_kept(&uninstall_plan.kept);
 
-                tui::print_presets_success();
+            if let Some(wine_prefix) = wine_prefix {
+                let prefix = PathBuf::from(&wine_prefix);
+                // the overrides for the whole prefix are needed as long as other games run in it
+                let users = match wine_executable {
+                    Some(_) => Vec::new(),
+                    None => games_in_prefix(&manager.game_paths(), &prefix),
+                };
+                if users.is_empty() {
+                    let _lock = manager.lock()?;
+                    remove_dll_overrides(&prefix, wine_executable.as_deref(), &RESHADE_DLLS)?;
+                    tui::print_dll_overrides_removed(&wine_prefix);
+                } else {
+                    tui::print_dll_overrides_kept(&wine_prefix, &users);
+                }
+            }
+        }
+        cli::SubCommand::ApplyPlan { plan } => {
+            let content = std::fs::read_to_string(plan)?;
+            let plans = serde_json::from_str::<Vec<Plan>>(&content)
+                .map_err(|e| ReShaderError::Plan(e.to_string()))?;
+            tui::print_plans(&plans);
+            for plan in &plans {
+                manager.apply_plan(plan)?;
+            }
+            tui::print_plans_applied();
+        }
+        cli::SubCommand::Run { game, command } => {
+            let game_path = match game {
+                Some(game) => Some(game_directory(manager, &game)?),
+                None => {
+                    let game_paths = manager.game_paths();
+                    resolve_game_path(&game_paths, &std::env::current_dir()?, &command)
+                }
+            };
+
+            match game_path {
+                Some(game_path) => match manager.repair(&game_path).await {
+                    Ok(true) => tui::print_reshade_repaired(&game_path),
+                    Ok(false) => {}
+                    Err(e) => tui::print_run_warning(e),
+                },
+                None => tui::print_run_no_game(),
             }
+
+            let mut command =
+                prepare_command(&command, &RESHADE_DLLS).expect("clap requires a command");
+            // exec only returns if the command could not be started
+            return Err(command.exec().into());
         }
-        cli::SubCommand::Uninstall { game } => {
-            let game_path = PathBuf::from(game);
-            uninstall(&game_path)?;
+        cli::SubCommand::Update { game, all: _ } => {
+            let game_paths = match game {
+                Some(game) => vec![game_directory(manager, &game)?],
+                None => manager.game_paths(),
+            };
+            if game_paths.is_empty() {
+                tui::print_no_game_paths();
+                return Ok(());
+            }
 
-            config
-                .game_paths
-                .retain(|path| path != &game_path.to_str().unwrap().to_string());
+            tui::print_updating();
+            let updates = manager
+                .update(client, &game_paths, &specific_installer)
+                .await?;
+            tui::print_updates(&updates);
+            if updates.iter().any(GameUpdate::failed) {
+                manager.save()?;
+                exit(1);
+            }
         }
+        cli::SubCommand::Status { game, json } => {
+            let statuses = match game {
+                Some(game) => {
+                    let game_path = game_directory(manager, &game)?;
+                    let status = game_status(data_dir, &game_path);
+                    vec![(game_path, status)]
+                }
+                None => manager.statuses(),
+            };
+
+            if json {
+                let statuses = statuses
+                    .iter()
+                    .map(|(game_path, status)| match status {
+                        Ok(status) => serde_json::to_value(status),
+                        Err(e) => Ok(serde_json::json!({
+                            "game_path": game_path,
+                            "error": e.to_string(),
+                        })),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .expect("statuses can always be serialized");
+                let json = serde_json::to_string_pretty(&statuses)
+                    .expect("statuses can always be serialized");
+                println!("{json}");
+            } else {
+                tui::print_statuses(&statuses);
+            }
+        }
+        cli::SubCommand::Doctor {
+            game,
+            wine_prefix,
+            fix,
+        } => {
+            let game_paths = match game {
+                Some(game) => vec![game_directory(manager, &game)?],
+                None => manager.game_paths(),
+            };
+            if game_paths.is_empty() {
+                tui::print_doctor_no_games();
+                return Ok(());
+            }
+
+            let wine_prefix = wine_prefix.map(PathBuf::from);
+            let mut healthy = true;
+            for game_path in &game_paths {
+                // a game that can't be checked doesn't keep the others from being checked
+                let mut problems = match diagnose(data_dir, game_path, wine_prefix.as_deref()) {
+                    Ok(problems) => problems,
+                    Err(e) => {
+                        tui::print_diagnose_failed(game_path, e);
+                        healthy = false;
+                        continue;
+                    }
+                };
+                if fix {
+                    let _lock = manager.lock()?;
+                    let link_mode = manager.config().link_mode(game_path);
+                    for problem in problems.iter().filter(|problem| problem.is_fixable()) {
+                        if let Err(e) = fix_problem(data_dir, game_path, problem, link_mode).await {
+                            tui::print_fix_failed(problem, e);
+                        }
+                    }
+                    let remaining = match diagnose(data_dir, game_path, wine_prefix.as_deref()) {
+                        Ok(remaining) => remaining,
+                        Err(e) => {
+                            tui::print_diagnose_failed(game_path, e);
+                            healthy = false;
+                            continue;
+                        }
+                    };
+                    let fixed = problems
+                        .iter()
+                        .filter(|problem| !remaining.contains(problem))
+                        .count();
+                    tui::print_problems_fixed(game_path, fixed);
+                    problems = remaining;
+                }
+                tui::print_problems(game_path, &problems, !fix);
+                healthy &= problems.is_empty();
+            }
+            if !healthy {
+                exit(1);
+            }
+        }
+        cli::SubCommand::Gc { yes } => {
+            let artifacts = manager.find_garbage()?;
+            tui::print_artifacts(&artifacts);
+            if artifacts.is_empty() || !(yes || tui::prompt_remove_artifacts()?) {
+                return Ok(());
+            }
+            let freed = manager.remove_garbage(&artifacts)?;
+            tui::print_artifacts_removed(freed);
+        }
+        cli::SubCommand::Ini { action } => ini(action, manager)?,
+        cli::SubCommand::Presets { action } => presets(action, manager)?,
+        cli::SubCommand::Shaders { action } => shaders(action, manager)?,
     }
 
-    let config_str =
-        toml::to_string(&config).expect("if you see this error, the toml library is broken");
-    std::fs::write(config_path, config_str)?;
+    manager.save()?;
 
     Ok(())
 }
@@ -361,59 +1002,34 @@ async fn main() -> InquireResult<()> {
         exit(1);
     }
 
-    let dirs = directories::ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION);
-    if dirs.is_none() {
-        tui::print_no_home_dir();
-        exit(1);
-    }
-    let dirs = dirs.unwrap();
-
-    std::fs::create_dir_all(dirs.config_dir())?;
-    std::fs::create_dir_all(dirs.data_dir())?;
-
-    let config_dir = dirs.config_dir().to_path_buf();
-    let data_dir = dirs.data_dir().to_path_buf();
+    let args = cli::CliArgs::parse();
+    let specific_installer = args.use_installer;
 
-    let config_path = config_dir.join("config.toml");
-    let mut config = if config_path.exists() {
-        let config_str = std::fs::read_to_string(&config_path)?;
-        let result = toml::from_str(&config_str);
-        if result.is_err() {
-            tui::print_config_deserialization_error();
+    let mut manager = match ReShaderManager::open() {
+        Ok(manager) => manager,
+        Err(ReShaderError::NoHomeDirectory) => {
+            tui::print_no_home_dir();
             exit(1);
         }
-        result.unwrap()
-    } else {
-        let config = Config::default();
-        let config_str =
-            toml::to_string(&config).expect("if you see this error, the toml library is broken");
-        std::fs::write(&config_path, config_str)?;
-        config
+        Err(e @ ReShaderError::Config(_)) => {
+            tui::print_config_deserialization_error(e);
+            exit(1);
+        }
+        Err(e) => return Err(e.into()),
     };
+    manager.on_lock_wait(tui::print_waiting_for_lock);
+    if manager.recovered() > 0 {
+        tui::print_transactions_recovered(manager.recovered());
+    }
+    if let Some(backup) = manager.migrated_from() {
+        tui::print_config_migrated(backup);
+    }
     let client = reqwest::Client::new();
 
-    let args = cli::CliArgs::parse();
-    let specific_installer = args.use_installer;
-
     if let Some(subcommand) = args.subcommand {
-        cli(
-            subcommand,
-            &mut config,
-            &client,
-            &data_dir,
-            &config_path,
-            specific_installer,
-        )
-        .await?;
+        cli(subcommand, &mut manager, &client, specific_installer).await?;
     } else {
-        tui(
-            &mut config,
-            &client,
-            &data_dir,
-            &config_path,
-            specific_installer,
-        )
-        .await?;
+        tui(&mut manager, &client, specific_installer).await?;
     }
 
     Ok(())
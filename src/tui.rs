@@ -1,12 +1,49 @@
-use std::path::{Path, PathBuf};
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use colored::Colorize;
 use inquire::{error::InquireResult, InquireError, Text};
-use reshaderlib::ShaderCollection;
+use reshaderlib::{
+    catalog::{CatalogMatch, ShaderCatalog},
+    collection_index::PresetResolution,
+    doctor::Problem,
+    executable::{find_executables, GameExecutable},
+    gc::Artifact,
+    gshade::{ConversionReport, DEFAULT_PRESETS_SUBFOLDER},
+    link::LinkMode,
+    original_dll_path,
+    plan::Plan,
+    prelude::{ReShaderError, ReShaderResult},
+    preset_store::StoredPreset,
+    status::GameStatus,
+    update::{GameUpdate, UpdateOutcome},
+    validate::PresetIssue,
+    ShaderCollection,
+};
+use strum::IntoEnumIterator;
+
+enum InstallDirectoryChoice {
+    Executable(GameExecutable),
+    Folder(PathBuf),
+}
+
+impl Display for InstallDirectoryChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallDirectoryChoice::Executable(executable) => write!(f, "{executable}"),
+            InstallDirectoryChoice::Folder(path) => {
+                write!(f, "None of these, install into {}", path.display())
+            }
+        }
+    }
+}
 
 pub fn prompt_game_path() -> InquireResult<PathBuf> {
     let game_path = Text::new("Enter the path to your ReShade-supported game")
-        .with_help_message("This is the folder containing the game executable, e.g. ~/.xlcore/ffxiv/game. Please note that ReShade might not work with unsupported games.")
+        .with_help_message("This is the folder of the game, e.g. ~/.xlcore/ffxiv/game. ReShader will look for the game executable inside it. Please note that ReShade might not work with unsupported games.")
         .with_default("~/.xlcore/ffxiv/game")
         .with_validator(|input: &str| {
             if input.is_empty() {
@@ -22,6 +59,28 @@ pub fn prompt_game_path() -> InquireResult<PathBuf> {
     Ok(std::path::Path::new(&game_path).to_path_buf())
 }
 
+pub fn prompt_install_directory(game_path: &Path) -> InquireResult<PathBuf> {
+    let executables = find_executables(game_path)?;
+    if executables.is_empty() {
+        return Ok(game_path.to_path_buf());
+    }
+
+    let mut choices = executables
+        .into_iter()
+        .take(10)
+        .map(InstallDirectoryChoice::Executable)
+        .collect::<Vec<_>>();
+    choices.push(InstallDirectoryChoice::Folder(game_path.to_path_buf()));
+
+    let choice = inquire::Select::new("Select the game executable", choices)
+        .with_help_message("ReShade will be installed next to this executable. The most likely executable is listed first.")
+        .prompt()?;
+    match choice {
+        InstallDirectoryChoice::Executable(executable) => Ok(executable.directory().to_path_buf()),
+        InstallDirectoryChoice::Folder(path) => Ok(path),
+    }
+}
+
 pub fn prompt_open_links() -> InquireResult<bool> {
     inquire::Confirm::new("Do you want to open these download links now?")
         .with_help_message("If not, the installer will assume you have the links already present.")
@@ -37,12 +96,120 @@ pub fn prompt_install() -> InquireResult<bool> {
 }
 
 pub fn prompt_confirm_move() -> InquireResult<bool> {
-    inquire::Confirm::new("Have you downloaded the files and put them in the correct directory?")
+    inquire::Confirm::new("Have you downloaded the files?")
         .with_help_message("Answering no will cancel the installation.")
         .with_default(true)
         .prompt()
 }
 
+struct LinkModeChoice(LinkMode);
+
+impl Display for LinkModeChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            LinkMode::Symlink => write!(f, "Symlink (recommended)"),
+            LinkMode::RelativeSymlink => write!(f, "Relative symlink"),
+            LinkMode::Hardlink => write!(f, "Hardlink (same filesystem only)"),
+            LinkMode::Reflink => write!(f, "Reflink (Btrfs, XFS)"),
+            LinkMode::Copy => write!(f, "Copy"),
+        }
+    }
+}
+
+pub fn prompt_link_mode(default: LinkMode) -> InquireResult<LinkMode> {
+    let choices = LinkMode::iter().map(LinkModeChoice).collect::<Vec<_>>();
+    let cursor = choices
+        .iter()
+        .position(|choice| choice.0 == default)
+        .unwrap_or_default();
+    let choice = inquire::Select::new("How should ReShade be put into the game folder?", choices)
+        .with_help_message("Use a copy or a hardlink if the game runs in a sandbox like Flatpak or is on another drive.")
+        .with_starting_cursor(cursor)
+        .prompt()?;
+    Ok(choice.0)
+}
+
+pub fn prompt_chain_load() -> InquireResult<bool> {
+    inquire::Confirm::new("Should ReShade load the original dxgi.dll after itself?")
+        .with_help_message("Answer yes if the game needs it, e.g. because it is DXVK.")
+        .with_default(true)
+        .prompt()
+}
+
+pub fn prompt_use_gshade_download(content: &str, path: &Path) -> InquireResult<bool> {
+    inquire::Confirm::new(&format!(
+        "Found GShade {content} in {}, do you want to install them?",
+        path.display()
+    ))
+    .with_help_message("Answering no lets you enter the path yourself.")
+    .with_default(true)
+    .prompt()
+}
+
+fn prompt_gshade_path(
+    message: &str,
+    help: &str,
+    default: Option<PathBuf>,
+) -> InquireResult<PathBuf> {
+    let mut prompt = Text::new(message)
+        .with_help_message(help)
+        .with_validator(|input: &str| {
+            if input.is_empty() {
+                return Ok(inquire::validator::Validation::Invalid(
+                    inquire::validator::ErrorMessage::Custom("Please enter a path!".to_string()),
+                ));
+            }
+            if !Path::new(shellexpand::tilde(input).as_ref()).exists() {
+                return Ok(inquire::validator::Validation::Invalid(
+                    inquire::validator::ErrorMessage::Custom(
+                        "The path you entered does not exist!".to_string(),
+                    ),
+                ));
+            }
+            Ok(inquire::validator::Validation::Valid)
+        });
+    let default = default.map(|default| default.to_string_lossy().to_string());
+    if let Some(default) = &default {
+        prompt = prompt.with_default(default);
+    }
+
+    let path = prompt.prompt()?;
+    Ok(PathBuf::from(shellexpand::tilde(&path).to_string()))
+}
+
+pub fn prompt_gshade_presets_path(data_dir: &Path) -> InquireResult<PathBuf> {
+    let default = data_dir.join("GShade-Presets-master.zip");
+    prompt_gshade_path(
+        "Enter the path to the GShade presets",
+        "This can be the downloaded zip file, an extracted folder, a git checkout or a folder with presets.",
+        default.exists().then_some(default),
+    )
+}
+
+pub fn prompt_select_presets_subfolder(subfolders: &[String]) -> InquireResult<Option<String>> {
+    let all = "All presets".to_string();
+    let mut options = vec![all.clone()];
+    options.extend(subfolders.iter().cloned());
+    let ffxiv = subfolders
+        .iter()
+        .position(|subfolder| subfolder.eq_ignore_ascii_case(DEFAULT_PRESETS_SUBFOLDER))
+        .map_or(0, |index| index + 1);
+    let subfolder = inquire::Select::new("Which presets do you want to install?", options)
+        .with_help_message("The GShade presets contain presets for several games.")
+        .with_starting_cursor(ffxiv)
+        .prompt()?;
+    Ok((subfolder != all).then_some(subfolder))
+}
+
+pub fn prompt_gshade_shaders_path(data_dir: &Path) -> InquireResult<PathBuf> {
+    let default = data_dir.join("GShade-C-Shaders-main.zip");
+    prompt_gshade_path(
+        "Enter the path to the GShade shaders",
+        "This can be the downloaded zip file, an extracted folder, a git checkout or a folder containing the Shaders folder.",
+        default.exists().then_some(default),
+    )
+}
+
 pub fn prompt_install_presets_for_games() -> InquireResult<bool> {
     inquire::Confirm::new("Do you want to install the preset and shaders for games now?")
         .with_help_message("This simplifies the configuration of GShade for the game.")
@@ -50,22 +217,32 @@ pub fn prompt_install_presets_for_games() -> InquireResult<bool> {
         .prompt()
 }
 
-pub fn prompt_select_game_paths(paths: Vec<String>) -> InquireResult<Vec<String>> {
-    inquire::MultiSelect::new(
+pub fn prompt_select_game_paths(paths: &[PathBuf]) -> InquireResult<Vec<PathBuf>> {
+    let game_paths = inquire::MultiSelect::new(
         "Select the games you want to install the shaders and presets for",
-        paths,
+        path_strings(paths),
     )
-    .prompt()
+    .prompt()?;
+    Ok(game_paths.into_iter().map(PathBuf::from).collect())
 }
 
-pub fn prompt_select_game_path_uninstall(paths: Vec<String>) -> InquireResult<PathBuf> {
-    let game_path =
-        inquire::Select::new("Select the game you want to uninstall ReShade from", paths)
-            .prompt()?;
+pub fn prompt_select_game_path_uninstall(paths: &[PathBuf]) -> InquireResult<PathBuf> {
+    let game_path = inquire::Select::new(
+        "Select the game you want to uninstall ReShade from",
+        path_strings(paths),
+    )
+    .prompt()?;
     let game_path = shellexpand::tilde(&game_path).to_string();
     Ok(std::path::Path::new(&game_path).to_path_buf())
 }
 
+fn path_strings(paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| path.to_str().unwrap().to_string())
+        .collect()
+}
+
 pub fn prompt_install_shaders() -> InquireResult<bool> {
     inquire::Confirm::new("Do you want to install the shaders now?")
         .with_help_message("Answering no will return to the main menu.")
@@ -73,12 +250,13 @@ pub fn prompt_install_shaders() -> InquireResult<bool> {
         .prompt()
 }
 
-pub fn prompt_select_game_paths_shaders(paths: Vec<String>) -> InquireResult<Vec<String>> {
-    inquire::MultiSelect::new(
+pub fn prompt_select_game_paths_shaders(paths: &[PathBuf]) -> InquireResult<Vec<PathBuf>> {
+    let game_paths = inquire::MultiSelect::new(
         "Select the games you want to install the shaders for",
-        paths,
+        path_strings(paths),
     )
-    .prompt()
+    .prompt()?;
+    Ok(game_paths.into_iter().map(PathBuf::from).collect())
 }
 
 pub fn prompt_select_select_shaders(
@@ -115,13 +293,15 @@ pub fn print_gshade_warning() {
     println!();
 }
 
-pub fn print_gshade_file_move(directory: &Path) {
+pub fn print_gshade_file_location() {
     println!();
     println!(
-        "{} {} {}",
-        "After you have downloaded the files, please put them in the".cyan(),
-        directory.to_str().unwrap().white().bold(),
-        "directory, named \"shaders.zip\" and \"presets.zip\".".cyan()
+        "{}",
+        "ReShader will look for the files in your downloads folder. If they are somewhere else, ReShader will ask you where you saved them.".cyan()
+    );
+    println!(
+        "{}",
+        "Instead of the zip files, you can also use extracted folders, git checkouts or the gshade folders of a game.".cyan()
     );
     println!();
 }
@@ -136,10 +316,10 @@ pub fn print_gshade_hint() {
         "{}\n{}",
         "https://gitlab.com/Mortalitas/GShade-C-Shaders"
             .white()
-            .bold(), 
+            .bold(),
         "https://gitlab.com/Mortalitas/GShade-Presets"
             .white()
-            .bold(), 
+            .bold(),
     );
     println!();
     println!(
@@ -191,16 +371,24 @@ pub fn print_no_home_dir() {
     println!();
 }
 
-pub fn print_config_deserialization_error() {
+pub fn print_config_deserialization_error(error: ReShaderError) {
     println!();
     println!(
         "{}",
-        "Could not deserialize the configuration file. Please make sure it is valid and try again."
+        format!("Could not deserialize the configuration file ({error}). Please make sure it is valid and try again.")
             .bright_red()
     );
     println!();
 }
 
+pub fn print_config_migrated(backup: &Path) {
+    eprintln!(
+        "{} {}",
+        "Updated the configuration file to the new format, the old one was backed up to".cyan(),
+        backup.to_str().unwrap().white().bold()
+    );
+}
+
 pub fn print_error(error: InquireError) {
     println!();
     println!("{}", format!("An error occurred: {error}").bright_red());
@@ -227,3 +415,627 @@ pub fn print_shader_download_successful() {
     println!("{}", "Successfully downloaded ReShade shaders! To install them, run this option again and select a game!".bright_green());
     println!();
 }
+
+pub fn print_dll_overrides_set(prefix: &str) {
+    println!();
+    println!(
+        "{} {}{}",
+        "Added DLL overrides for ReShade to the Wine prefix at".bright_green(),
+        prefix.white().bold(),
+        ".".bright_green()
+    );
+    println!();
+}
+
+pub fn print_dll_overrides_removed(prefix: &str) {
+    println!();
+    println!(
+        "{} {}{}",
+        "Removed the DLL overrides for ReShade from the Wine prefix at".bright_green(),
+        prefix.white().bold(),
+        ".".bright_green()
+    );
+    println!();
+}
+
+pub fn print_dll_overrides_kept(prefix: &str, game_paths: &[PathBuf]) {
+    println!();
+    println!(
+        "{} {} {}",
+        "Kept the DLL overrides for ReShade in the Wine prefix at".yellow(),
+        prefix.white().bold(),
+        "because these games still use it, pass --wine-executable to only remove the overrides of the game:"
+            .yellow()
+    );
+    for game_path in game_paths {
+        println!("  {}", game_path.to_str().unwrap().white().bold());
+    }
+    println!();
+}
+
+pub fn print_reshade_repaired(game_path: &Path) {
+    eprintln!(
+        "{} {}",
+        "Repaired the ReShade installation at".cyan(),
+        game_path.to_str().unwrap().white().bold()
+    );
+}
+
+pub fn print_transactions_recovered(count: usize) {
+    eprintln!(
+        "{}",
+        format!("ReShader was interrupted last time, {count} unfinished operation(s) have been rolled back.").yellow()
+    );
+}
+
+pub fn print_waiting_for_lock() {
+    eprintln!(
+        "{}",
+        "Another ReShader process is changing the data directory, waiting for it to finish..."
+            .yellow()
+    );
+}
+
+pub fn print_run_warning(error: ReShaderError) {
+    eprintln!(
+        "{}",
+        format!("Could not check the ReShade installation, launching anyway: {error}").yellow()
+    );
+}
+
+pub fn print_statuses(statuses: &[(PathBuf, ReShaderResult<GameStatus>)]) {
+    if statuses.is_empty() {
+        println!(
+            "{}",
+            "ReShade hasn't been installed for any game yet.".yellow()
+        );
+        return;
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let header = [
+        "Game", "ReShade", "Variant", "Proxy", "Shaders", "Presets", "Links", "Updated",
+    ];
+    let rows = statuses
+        .iter()
+        .filter_map(|(_, status)| status.as_ref().ok())
+        .map(|status| {
+            [
+                status.game_path.display().to_string(),
+                status.version.clone().unwrap_or_else(|| "-".to_string()),
+                status
+                    .variant
+                    .map(|variant| variant.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                status.proxy.clone().unwrap_or_else(|| "-".to_string()),
+                status.shaders.to_string(),
+                status
+                    .preset_folder
+                    .as_ref()
+                    .map(|folder| folder.display().to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                status.links.to_string(),
+                status
+                    .updated
+                    .map(|updated| format_age(now.saturating_sub(updated)))
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = header
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:<width$}"))
+        .collect::<Vec<_>>();
+    println!("{}", line.join("  ").trim_end().white().bold());
+    let checked = statuses
+        .iter()
+        .filter_map(|(_, status)| status.as_ref().ok());
+    for (row, status) in rows.iter().zip(checked) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        if status.links.broken > 0 {
+            println!("{}", line.trim_end().yellow());
+        } else {
+            println!("{}", line.trim_end());
+        }
+    }
+    for (game_path, status) in statuses {
+        if let Err(e) = status {
+            println!(
+                "{} {}",
+                game_path.to_str().unwrap(),
+                format!("could not be checked: {e}").red()
+            );
+        }
+    }
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
+
+pub fn print_doctor_no_games() {
+    println!(
+        "{}",
+        "ReShade hasn't been installed for any game yet.".yellow()
+    );
+}
+
+pub fn print_problems(game_path: &Path, problems: &[Problem], suggest_fix: bool) {
+    if problems.is_empty() {
+        println!(
+            "{} {}",
+            game_path.to_str().unwrap().white().bold(),
+            "looks good.".bright_green()
+        );
+        return;
+    }
+
+    println!(
+        "{} {}",
+        game_path.to_str().unwrap().white().bold(),
+        "has problems:".yellow()
+    );
+    for problem in problems {
+        println!("  {problem}");
+        println!("    {} {}", "->".cyan(), problem.suggestion(game_path));
+    }
+    if suggest_fix && problems.iter().any(Problem::is_fixable) {
+        println!(
+            "{}",
+            "Run `reshader doctor --fix` to fix what can be fixed automatically.".cyan()
+        );
+    }
+}
+
+pub fn print_problems_fixed(game_path: &Path, fixed: usize) {
+    if fixed > 0 {
+        println!(
+            "{} {} {} {}",
+            "Fixed".bright_green(),
+            fixed.to_string().white().bold(),
+            "problem(s) of".bright_green(),
+            game_path.to_str().unwrap().white().bold()
+        );
+    }
+}
+
+pub fn print_diagnose_failed(game_path: &Path, error: ReShaderError) {
+    eprintln!(
+        "{} {}",
+        game_path.to_str().unwrap().white().bold(),
+        format!("could not be checked: {error}").red()
+    );
+}
+
+pub fn print_fix_failed(problem: &Problem, error: ReShaderError) {
+    eprintln!(
+        "{}",
+        format!("Could not fix \"{problem}\": {error}").yellow()
+    );
+}
+
+pub fn print_run_no_game() {
+    eprintln!(
+        "{}",
+        "Could not find the game for this command, launching without checking ReShade.".yellow()
+    );
+}
+
+pub fn print_executable_detected(executable: &GameExecutable) {
+    println!();
+    println!(
+        "{} {}",
+        "Detected game executable:".cyan(),
+        executable.to_string().white().bold()
+    );
+    println!();
+}
+
+pub fn print_no_reshade_ini(game_path: &Path) {
+    println!(
+        "{} {}",
+        game_path.to_str().unwrap().white().bold(),
+        "has no ReShade.ini, skipping.".yellow()
+    );
+}
+
+pub fn print_ini_value(game_path: &Path, value: Option<&str>) {
+    match value {
+        Some(value) => println!("{}: {}", game_path.to_str().unwrap().white().bold(), value),
+        None => println!(
+            "{}: {}",
+            game_path.to_str().unwrap().white().bold(),
+            "(not set)".dimmed()
+        ),
+    }
+}
+
+pub fn print_ini_changed(game_path: &Path) {
+    println!(
+        "{} {}",
+        "Updated the ReShade.ini of".bright_green(),
+        game_path.to_str().unwrap().white().bold()
+    );
+}
+
+pub fn print_ini_unchanged(game_path: &Path) {
+    println!(
+        "{} {}",
+        "Nothing to change in the ReShade.ini of".cyan(),
+        game_path.to_str().unwrap().white().bold()
+    );
+}
+
+pub fn print_indexing_collections() {
+    println!();
+    println!(
+        "{}",
+        "Downloading all shader collections to find out which effects they contain. This might take a while...".cyan()
+    );
+    println!();
+}
+
+pub fn print_preset_resolution(resolution: &PresetResolution) {
+    println!();
+    println!("{}", "The preset needs these shader collections:".cyan());
+    for collection in &resolution.collections {
+        println!("  {}", collection.name.white().bold());
+    }
+    if !resolution.unsatisfied.is_empty() {
+        println!();
+        println!(
+            "{}",
+            "No known shader collection provides these techniques:".yellow()
+        );
+        for technique in &resolution.unsatisfied {
+            println!("  {}", technique.to_string().white().bold());
+        }
+    }
+    println!();
+}
+
+pub fn print_catalog_scanned(catalog: &ShaderCatalog) {
+    let techniques = catalog
+        .effects
+        .iter()
+        .map(|effect| effect.info.techniques.len())
+        .sum::<usize>();
+    println!(
+        "{} {} {} {} {}",
+        "Found".bright_green(),
+        techniques.to_string().white().bold(),
+        "techniques in".bright_green(),
+        catalog.effects.len().to_string().white().bold(),
+        "effect files.".bright_green()
+    );
+}
+
+pub fn print_catalog_matches(matches: &[CatalogMatch]) {
+    if matches.is_empty() {
+        println!(
+            "{}",
+            "No shaders found. If you downloaded shaders since the last search, try --rescan."
+                .yellow()
+        );
+        return;
+    }
+
+    for found in matches {
+        let collection = found
+            .effect
+            .collection
+            .as_deref()
+            .unwrap_or("unknown collection");
+        println!(
+            "{} {}",
+            format!("{}@{}", found.technique.name, found.effect.file)
+                .white()
+                .bold(),
+            format!("({collection})").dimmed()
+        );
+        if let Some(label) = found.technique.label() {
+            println!("  {}", label.cyan());
+        }
+        if let Some(tooltip) = found.technique.tooltip() {
+            for line in tooltip.lines() {
+                println!("  {line}");
+            }
+        }
+        println!("  {}", found.effect.path.display().to_string().dimmed());
+    }
+}
+
+pub fn print_preset_issues(issues: &[PresetIssue]) {
+    if issues.is_empty() {
+        println!(
+            "{}",
+            "Everything the preset uses is installed.".bright_green()
+        );
+        return;
+    }
+
+    println!("{}", "The preset won't work as expected:".yellow());
+    for issue in issues {
+        println!("  {issue}");
+    }
+}
+
+pub fn print_presets_imported(presets: &[StoredPreset]) {
+    if presets.is_empty() {
+        println!("{}", "No presets found to import.".yellow());
+        return;
+    }
+
+    println!(
+        "{} {} {}",
+        "Imported".bright_green(),
+        presets.len().to_string().white().bold(),
+        "presets:".bright_green()
+    );
+    print_stored_presets(presets);
+}
+
+pub fn print_stored_presets(presets: &[StoredPreset]) {
+    if presets.is_empty() {
+        println!("{}", "No presets found.".yellow());
+    }
+    for preset in presets {
+        println!("  {}", preset.name.white().bold());
+    }
+}
+
+pub fn print_preset_exported(destination: &Path) {
+    println!(
+        "{} {}",
+        "Exported the preset to".bright_green(),
+        destination.to_str().unwrap().white().bold()
+    );
+}
+
+pub fn print_conversion_reports(
+    reports: &[(PathBuf, ConversionReport)],
+    indexed: bool,
+    cataloged: bool,
+) {
+    let mut complete = 0;
+    for (path, report) in reports {
+        if report.unsupported.is_empty() && report.unknown.is_empty() && report.unmapped.is_empty()
+        {
+            complete += 1;
+            continue;
+        }
+
+        println!("{}", path.to_str().unwrap().white().bold());
+        for (file, reason) in &report.unsupported {
+            println!("  {} {}", file.yellow(), reason);
+        }
+        for technique in &report.unknown {
+            println!(
+                "  {} is not part of any known shader collection",
+                technique.to_string().yellow()
+            );
+        }
+        for (file, uniform) in &report.unmapped {
+            let reason = if cataloged {
+                "doesn't declare it"
+            } else {
+                "may not declare it"
+            };
+            println!(
+                "  {} has no replacement in the mapping table and {} {}",
+                uniform.yellow(),
+                file,
+                reason
+            );
+        }
+    }
+
+    println!();
+    let changed = reports.iter().filter(|(_, report)| report.changed).count();
+    println!(
+        "{} {} {} {} {} {}",
+        "Converted".bright_green(),
+        changed.to_string().white().bold(),
+        "presets, unchanged:".bright_green(),
+        (reports.len() - changed).to_string().white().bold(),
+        "fully converted:".bright_green(),
+        complete.to_string().white().bold()
+    );
+    if !indexed {
+        println!(
+            "{}",
+            "Run install-preset-shaders once to also check the presets against the shader collections.".cyan()
+        );
+    }
+    if !cataloged {
+        println!(
+            "{}",
+            "Run shaders scan once to check the settings of replaced effects against the new effect files.".cyan()
+        );
+    }
+}
+
+pub fn print_plans(plans: &[Plan]) {
+    for plan in plans {
+        println!();
+        println!(
+            "{} {}",
+            "Changes to".yellow(),
+            plan.game_path.to_str().unwrap().white().bold()
+        );
+        if plan.is_empty() {
+            println!("  {}", "nothing to do".green());
+        }
+        for action in &plan.actions {
+            println!("  {action}");
+        }
+        for path in &plan.kept {
+            println!(
+                "  {} {}",
+                "keep (changed since ReShader created it, or holds settings)".yellow(),
+                path.to_str().unwrap()
+            );
+        }
+    }
+    println!();
+}
+
+pub fn print_plans_applied() {
+    println!("{}", "All changes have been applied!".green());
+}
+
+pub fn prompt_apply_plan() -> InquireResult<bool> {
+    inquire::Confirm::new("Do you want to apply these changes?")
+        .with_help_message("Answering no will return to the main menu.")
+        .with_default(true)
+        .prompt()
+}
+
+pub fn print_foreign_dlls(paths: &[PathBuf]) {
+    if paths.is_empty() {
+        return;
+    }
+
+    println!();
+    println!(
+        "{}",
+        "These files were not created by ReShader, they will be renamed and restored on uninstall:"
+            .yellow()
+    );
+    for path in paths {
+        println!(
+            "  {} -> {}",
+            path.to_str().unwrap().white().bold(),
+            original_dll_path(path).to_str().unwrap().white().bold()
+        );
+    }
+    println!();
+}
+
+pub fn print_uninstall_kept(paths: &[PathBuf]) {
+    if paths.is_empty() {
+        return;
+    }
+
+    println!();
+    println!(
+        "{}",
+        "These files were changed after ReShader created them or hold your settings and have not been removed:"
+            .yellow()
+    );
+    for path in paths {
+        println!("  {}", path.to_str().unwrap().white().bold());
+    }
+    println!();
+}
+
+pub fn print_updating() {
+    println!();
+    println!("{}", "Updating ReShade, shaders and presets...".cyan());
+}
+
+pub fn print_updates(updates: &[GameUpdate]) {
+    for update in updates {
+        println!();
+        println!("{}", update.game_path.display().to_string().bold());
+        if update.steps.is_empty() {
+            println!("  nothing installed");
+        }
+        for (step, outcome) in &update.steps {
+            match outcome {
+                UpdateOutcome::Updated(change) => println!("  {step}: {}", change.green()),
+                UpdateOutcome::Unchanged(state) => println!("  {step}: {state}"),
+                UpdateOutcome::Skipped(reason) => println!("  {step}: {}", reason.yellow()),
+                UpdateOutcome::Failed(reason) => println!("  {step}: {}", reason.red()),
+            }
+        }
+    }
+
+    let changed = updates.iter().filter(|update| update.changed()).count();
+    let failed = updates.iter().filter(|update| update.failed()).count();
+    println!();
+    if failed == 0 {
+        println!(
+            "{}",
+            format!("{changed} of {} game(s) updated.", updates.len()).green()
+        );
+    } else {
+        println!(
+            "{}",
+            format!(
+                "{changed} of {} game(s) updated, {failed} could not be updated completely.",
+                updates.len()
+            )
+            .yellow()
+        );
+    }
+}
+
+pub fn print_artifacts(artifacts: &[Artifact]) {
+    if artifacts.is_empty() {
+        println!("{}", "Nothing to clean up, every file is needed.".green());
+        return;
+    }
+
+    for artifact in artifacts {
+        println!(
+            "{:>10}  {} ({})",
+            format_size(artifact.size),
+            artifact.path.display(),
+            artifact.kind
+        );
+    }
+    let total = artifacts.iter().map(|artifact| artifact.size).sum();
+    println!();
+    println!(
+        "{}",
+        format!("{} can be reclaimed.", format_size(total)).yellow()
+    );
+}
+
+pub fn prompt_remove_artifacts() -> InquireResult<bool> {
+    inquire::Confirm::new("Do you want to remove these files?")
+        .with_help_message("They are downloaded again when a game needs them.")
+        .with_default(false)
+        .prompt()
+}
+
+pub fn print_artifacts_removed(freed: u64) {
+    println!("{}", format!("Removed {}.", format_size(freed)).green());
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}